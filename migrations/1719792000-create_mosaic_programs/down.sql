@@ -0,0 +1 @@
+drop table if exists mosaic_programs;
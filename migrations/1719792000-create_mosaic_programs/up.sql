@@ -0,0 +1,19 @@
+create table mosaic_programs (
+    id uuid primary key default gen_random_uuid(),
+    slug text not null check (slug != ''),
+    user_id uuid not null references users (id) on delete cascade,
+
+    name text not null check (name != ''),
+
+    -- The share link fragment, which has the module and its settings (like the seed).
+    share text not null check (share != ''),
+
+    created_at timestamptz not null default current_timestamp,
+    updated_at timestamptz not null default current_timestamp,
+
+    unique (slug)
+);
+
+create index mosaic_programs_user_id on mosaic_programs (user_id);
+
+select manage_updated_at('mosaic_programs');
@@ -0,0 +1,7 @@
+drop index if exists mosaic_programs_public_created_at;
+
+alter table mosaic_programs
+    drop column if exists parent_id,
+    drop column if exists public;
+
+alter table users drop column if exists name;
@@ -0,0 +1,7 @@
+alter table users add column name text null default null;
+
+alter table mosaic_programs
+    add column public boolean not null default false,
+    add column parent_id uuid null default null references mosaic_programs (id) on delete set null;
+
+create index mosaic_programs_public_created_at on mosaic_programs (created_at) where public;
@@ -0,0 +1,11 @@
+create table trellis_secrets (
+    user_id uuid primary key references users (id) on delete cascade,
+
+    -- The user's OpenWeatherMap API key, encrypted by the server (the nonce, then the ciphertext).
+    open_weather bytea null default null,
+
+    created_at timestamptz not null default current_timestamp,
+    updated_at timestamptz not null default current_timestamp
+);
+
+select manage_updated_at('trellis_secrets');
@@ -0,0 +1,203 @@
+//! Test cases for rules: what `next` should return for a given neighborhood.
+//!
+//! ```text
+//! // A lone black cell stays black.
+//! #000000 #000000 #000000 #000000 #000000 #000000 #000000 #000000 #000000 -> #000000
+//! ```
+//!
+//! Each case is a line with the nine neighborhood values (in the same order as the params of
+//! `next`), then `->`, then the expected result. Values are written the same way as numbers in
+//! [`dsl`](crate::dsl): decimal, hex (`0xff0000ff`), or colors (`#ff0000` or `#ff000080`).
+//! Blank lines are skipped, and `//` starts a comment.
+//!
+//! Cases run at tick 0 in the upper-left corner, which is what host functions like `tick` and
+//! `random` see. For modules that keep state, every neighbor's state is the same as its color
+//! and the expected result is the new state.
+
+use crate::dsl::{self, Pos};
+use crate::{Interpreter, Neighborhood, Position};
+
+/// The cases to start from when a module doesn't have any yet.
+pub const EXAMPLE: &str = "\
+// p00 p01 p02 p10 p11 p12 p20 p21 p22 -> next
+#000000 #000000 #000000 #000000 #ffffff #000000 #000000 #000000 #000000 -> #ffffff
+";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Case {
+    /// The line the case is on, counting from 1.
+    pub line: usize,
+    pub neighbors: Neighborhood,
+    pub expected: u32,
+}
+
+/// What happened when running a case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    Fail { actual: u32 },
+    Error(String),
+}
+
+/// Reads every case in the text, stopping at the first one that's written wrong.
+pub fn parse(text: &str) -> Result<Vec<Case>, dsl::Error> {
+    let mut cases = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let code = line.split("//").next().unwrap_or_default();
+        if code.trim().is_empty() {
+            continue;
+        }
+
+        let pos = |offset: usize| Pos {
+            line: line_number,
+            column: code[..offset].chars().count() + 1,
+        };
+        let start = pos(code.len() - code.trim_start().len());
+
+        let Some((inputs, expected)) = code.split_once("->") else {
+            return Err(dsl::Error {
+                pos: start,
+                message: String::from("expected nine values, `->`, and the expected result"),
+            });
+        };
+
+        let mut values = Vec::with_capacity(9);
+        for (offset, word) in words(inputs) {
+            values.push(dsl::number(word, pos(offset))?);
+        }
+
+        let neighbors: [u32; 9] = values.try_into().map_err(|values: Vec<u32>| dsl::Error {
+            pos: start,
+            message: format!("expected nine neighborhood values, got {}", values.len()),
+        })?;
+
+        let arrow = inputs.len() + 2;
+        let expected = match words(expected)[..] {
+            [(offset, word)] => dsl::number(word, pos(arrow + offset))?,
+            _ => {
+                return Err(dsl::Error {
+                    pos: pos(arrow),
+                    message: String::from("expected one value after `->`"),
+                })
+            }
+        };
+
+        let [a, b, c, d, e, f, g, h, i] = neighbors;
+        cases.push(Case {
+            line: line_number,
+            neighbors: (a, b, c, d, e, f, g, h, i),
+            expected,
+        });
+    }
+
+    Ok(cases)
+}
+
+/// Runs each case against the interpreter.
+pub fn run(interpreter: &mut Interpreter, cases: &[Case]) -> Vec<Outcome> {
+    cases
+        .iter()
+        .map(
+            |case| match interpreter.eval(Position::default(), case.neighbors) {
+                Ok(actual) if actual == case.expected => Outcome::Pass,
+                Ok(actual) => Outcome::Fail { actual },
+                Err(err) => Outcome::Error(err.to_string()),
+            },
+        )
+        .collect()
+}
+
+/// Splits on whitespace, keeping the byte offset of each word.
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(s), true) => {
+                words.push((s, &text[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Module;
+
+    fn pos(line: usize, column: usize) -> Pos {
+        Pos { line, column }
+    }
+
+    #[test]
+    fn parses_cases() {
+        let cases = parse(EXAMPLE).unwrap();
+        assert_eq!(
+            cases,
+            [Case {
+                line: 2,
+                neighbors: (0xff, 0xff, 0xff, 0xff, 0xffffffff, 0xff, 0xff, 0xff, 0xff),
+                expected: 0xffffffff,
+            }]
+        );
+
+        let cases = parse("\n1 2 3 4 5 6 7 8 9 -> 0x10 // comment\n// skipped").unwrap();
+        assert_eq!(cases[0].line, 2);
+        assert_eq!(cases[0].neighbors, (1, 2, 3, 4, 5, 6, 7, 8, 9));
+        assert_eq!(cases[0].expected, 16);
+    }
+
+    #[test]
+    fn locates_mistakes() {
+        let err = parse("1 2 3").unwrap_err();
+        assert_eq!(err.pos, pos(1, 1));
+
+        let err = parse("\n  1 2 3 -> 4").unwrap_err();
+        assert_eq!(err.pos, pos(2, 3));
+        assert_eq!(err.message, "expected nine neighborhood values, got 3");
+
+        let err = parse("1 2 3 4 5 6 7 8 #12 -> 4").unwrap_err();
+        assert_eq!(err.pos, pos(1, 17));
+        assert_eq!(err.message, "color `#12` needs 6 or 8 hex digits");
+
+        let err = parse("1 2 3 4 5 6 7 8 9 -> x").unwrap_err();
+        assert_eq!(err.pos, pos(1, 22));
+
+        let err = parse("1 2 3 4 5 6 7 8 9 ->").unwrap_err();
+        assert_eq!(err.message, "expected one value after `->`");
+    }
+
+    #[test]
+    fn runs_cases() {
+        let module = Module::compile(crate::Language::Dsl, String::from(dsl::EXAMPLE)).unwrap();
+        let mut interpreter = Interpreter::new(&module.binary, 0).unwrap();
+
+        // The example rule XORs the center with the corners.
+        let cases = parse("1 0 2 0 8 0 4 0 0 -> 15\n1 0 2 0 8 0 4 0 0 -> 8").unwrap();
+        assert_eq!(
+            run(&mut interpreter, &cases),
+            [Outcome::Pass, Outcome::Fail { actual: 15 }]
+        );
+
+        let module = Module::new(String::from(
+            r#"(module (func (export "next")
+                (param i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+                unreachable))"#,
+        ))
+        .unwrap();
+        let mut interpreter = Interpreter::new(&module.binary, 0).unwrap();
+        let outcomes = run(&mut interpreter, &cases[..1]);
+        assert!(
+            matches!(&outcomes[0], Outcome::Error(err) if err.starts_with("trapped")),
+            "{outcomes:?}"
+        );
+    }
+}
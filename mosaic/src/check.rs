@@ -0,0 +1,258 @@
+//! Checks module source while it's being written, pointing at problems where possible.
+
+use crate::dsl::{self, Pos};
+use crate::{CompileError, Interpreter, Language, Module};
+
+/// The first problem found in some source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Where the problem is, if it's about one spot in the source (as opposed to something like a
+    /// missing export).
+    pub pos: Option<Pos>,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.pos {
+            Some(pos) => write!(
+                f,
+                "line {}, column {}: {}",
+                pos.line, pos.column, self.message
+            ),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+impl Diagnostic {
+    fn unlocated(err: impl std::fmt::Display) -> Self {
+        Self {
+            pos: None,
+            message: err.to_string(),
+        }
+    }
+}
+
+/// Compiles the source and makes sure the result could run as a rule, which is everything
+/// needed before submitting it.
+pub fn check(language: Language, text: &str) -> Result<Module, Diagnostic> {
+    let binary = match language {
+        Language::Wat => parse_wat(text)?,
+        Language::Dsl => {
+            let wat = dsl::compile(text).map_err(|err| Diagnostic {
+                pos: Some(err.pos),
+                message: err.message,
+            })?;
+            wat::parse_str(wat).map_err(Diagnostic::unlocated)?
+        }
+        Language::Wasm => return Err(Diagnostic::unlocated(CompileError::Binary)),
+    };
+
+    Interpreter::new(&binary, 0).map_err(Diagnostic::unlocated)?;
+
+    Ok(Module {
+        language,
+        text: text.to_owned(),
+        binary,
+    })
+}
+
+/// Does the same thing as [`wat::parse_str`], but keeps the error's location.
+fn parse_wat(text: &str) -> Result<Vec<u8>, Diagnostic> {
+    let located = |err: wast::Error| Diagnostic {
+        pos: Some(pos_at(text, err.span().offset())),
+        message: err.message(),
+    };
+
+    let buf = wast::parser::ParseBuffer::new(text).map_err(located)?;
+    let mut module = wast::parser::parse::<wast::Wat>(&buf).map_err(located)?;
+    module.encode().map_err(located)
+}
+
+/// Converts a byte offset into a line and column (counting characters).
+pub fn pos_at(text: &str, offset: usize) -> Pos {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+    Pos {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
+
+/// Converts a line and column (counting characters) back into a byte offset.
+pub fn offset_at(text: &str, pos: Pos) -> usize {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(pos.line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+
+    let line = &text[line_start..];
+    let column: usize = line
+        .chars()
+        .take_while(|c| *c != '\n')
+        .take(pos.column.saturating_sub(1))
+        .map(char::len_utf8)
+        .sum();
+
+    line_start + column
+}
+
+/// Finds the bracket paired with the one at `offset` (in bytes), if there is one there. Brackets
+/// in comments and strings don't count.
+pub fn matching_bracket(language: Language, text: &str, offset: usize) -> Option<usize> {
+    // A binary module's text is its disassembly, which is WAT.
+    let language = match language {
+        Language::Wasm => Language::Wat,
+        language => language,
+    };
+
+    let mut open = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|(_, c)| *c);
+
+        match (language, c, next) {
+            (Language::Wat, ';', Some(';')) | (Language::Dsl, '/', Some('/')) => {
+                while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+            }
+
+            (Language::Wat, '(', Some(';')) => {
+                let mut depth = 0;
+                while let Some((_, c)) = chars.next() {
+                    match (c, chars.peek().map(|(_, c)| *c)) {
+                        ('(', Some(';')) => depth += 1,
+                        (';', Some(')')) if depth == 0 => {
+                            chars.next();
+                            break;
+                        }
+                        (';', Some(')')) => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+
+            (Language::Wat, '"', _) => {
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+
+            (_, '(' | '[' | '{', _) => open.push((i, c)),
+
+            (_, ')' | ']' | '}', _) => {
+                let Some((j, o)) = open.pop() else {
+                    continue;
+                };
+
+                let pairs = matches!((o, c), ('(', ')') | ('[', ']') | ('{', '}'));
+                if pairs && j == offset {
+                    return Some(i);
+                }
+                if pairs && i == offset {
+                    return Some(j);
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(line: usize, column: usize) -> Option<Pos> {
+        Some(Pos { line, column })
+    }
+
+    #[test]
+    fn accepts_valid_modules() {
+        let module = check(Language::Wat, &Module::default().text).unwrap();
+        assert_eq!(module, Module::default());
+
+        let module = check(Language::Dsl, dsl::EXAMPLE).unwrap();
+        assert_eq!(module.language, Language::Dsl);
+    }
+
+    #[test]
+    fn locates_syntax_errors() {
+        let err = check(
+            Language::Wat,
+            "(module\n  (func (export \"next\")\n    (oops)))",
+        )
+        .unwrap_err();
+        assert_eq!(err.pos, pos(3, 6));
+        assert!(err.message.contains("unknown operator"), "{err}");
+
+        let err = check(Language::Wat, "(module\n  (func").unwrap_err();
+        assert_eq!(err.pos, pos(2, 8));
+
+        let err = check(Language::Dsl, "let x = 1\nnext = y").unwrap_err();
+        assert_eq!(err.pos, pos(2, 8));
+        assert_eq!(err.to_string(), "line 2, column 8: unknown name `y`");
+    }
+
+    #[test]
+    fn checks_next_signature() {
+        let err = check(Language::Wat, "(module)").unwrap_err();
+        assert_eq!(err.pos, None);
+        assert_eq!(err.message, "module does not export a `next` function");
+
+        let err = check(
+            Language::Wat,
+            "(module (func (export \"next\") (param i32)))",
+        );
+        let err = err.unwrap_err();
+        assert!(
+            err.message.starts_with("`next` must take nine i32 params"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn converts_positions() {
+        let text = "ab\ncé\n\nx";
+        for (offset, _) in text.char_indices() {
+            assert_eq!(offset_at(text, pos_at(text, offset)), offset);
+        }
+
+        assert_eq!(pos_at(text, 6), Pos { line: 2, column: 3 });
+        assert_eq!(offset_at(text, Pos { line: 4, column: 1 }), 8);
+    }
+
+    #[test]
+    fn matches_brackets() {
+        let text = "(module (func) ;; (\n  (; ) ;) \"(\" )";
+        let close = text.len() - 1;
+
+        assert_eq!(matching_bracket(Language::Wat, text, 0), Some(close));
+        assert_eq!(matching_bracket(Language::Wat, text, close), Some(0));
+        assert_eq!(matching_bracket(Language::Wat, text, 8), Some(13));
+        assert_eq!(matching_bracket(Language::Wat, text, 1), None);
+        assert_eq!(
+            matching_bracket(Language::Wat, text, 18),
+            None,
+            "in a comment"
+        );
+
+        let text = "next = if (p00) { 1 } else { 2 } // )";
+        assert_eq!(matching_bracket(Language::Dsl, text, 10), Some(14));
+        assert_eq!(matching_bracket(Language::Dsl, text, 20), Some(16));
+        assert_eq!(matching_bracket(Language::Dsl, "(]", 0), None);
+    }
+}
@@ -0,0 +1,860 @@
+//! A small expression language for rules, compiled to WAT so it runs like any other module.
+//!
+//! ```text
+//! // XOR the cell with its diagonal neighbors.
+//! next = p11 ^ p00 ^ p02 ^ p20 ^ p22
+//! ```
+//!
+//! A rule is any number of `let name = expr` bindings followed by `next = expr`. Every value is
+//! an `i32`, usually a packed RGBA color:
+//!
+//! - `p00` through `p22` are the neighborhood, the same as the params of `next`.
+//! - Numbers can be decimal, hex (`0xff0000ff`), or colors (`#ff0000` or `#ff000080`).
+//! - Arithmetic and bitwise operators work on the whole value: `+ - * / % & | ^ << >> ~`.
+//! - Channel operators work on each color channel separately: `.+` and `.-` stop at the ends of
+//!   the range, and `.*` multiplies as if 255 were 1.0. `c.r`, `c.g`, `c.b`, and `c.a` pick out
+//!   one channel.
+//! - Comparisons (`== != < <= > >=`) and logic (`&& || !`) produce 1 or 0.
+//! - `if cond { a } else { b }` picks a value. Any nonzero condition is true.
+//! - Host functions can be called by name, like `hsv(tick() % 360, 255, 255)`. The ones without
+//!   parameters can leave off the parentheses.
+//! - `//` starts a comment.
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+
+use crate::host;
+
+/// The rule to start from when switching to this language.
+pub const EXAMPLE: &str = "\
+// XOR the cell with its diagonal neighbors.
+next = p11 ^ p00 ^ p02 ^ p20 ^ p22
+";
+
+/// Where something is in the source, counting from 1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Pos {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("line {}, column {}: {message}", pos.line, pos.column)]
+pub struct Error {
+    pub pos: Pos,
+    pub message: String,
+}
+
+impl Error {
+    fn new(pos: Pos, message: impl Into<String>) -> Self {
+        Self {
+            pos,
+            message: message.into(),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Compiles a rule to the text of a module that exports `next`.
+pub fn compile(source: &str) -> Result<String> {
+    let tokens = lex(source)?;
+    let rule = Parser {
+        tokens,
+        i: 0,
+        depth: 0,
+    }
+    .rule()?;
+    Compiler::default().rule(&rule)
+}
+
+// Lexing
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Number(u32),
+    Punct(&'static str),
+    Eof,
+}
+
+/// Punctuation, with longer tokens first so they win over their prefixes.
+const PUNCTS: &[&str] = &[
+    "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", ".+", ".-", ".*", "=", "<", ">", "+", "-", "*",
+    "/", "%", "&", "|", "^", "~", "!", "(", ")", "{", "}", ",", ";", ".",
+];
+
+const KEYWORDS: &[&str] = &["let", "next", "if", "else"];
+
+fn lex(source: &str) -> Result<Vec<(Token, Pos)>> {
+    let mut tokens = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let mut rest = line;
+        let mut column = 1;
+
+        while let Some(c) = rest.chars().next() {
+            let pos = Pos {
+                line: i + 1,
+                column,
+            };
+
+            if c.is_whitespace() {
+                rest = &rest[c.len_utf8()..];
+                column += 1;
+                continue;
+            }
+
+            // Identifiers, numbers, and punctuation are all ASCII, so their lengths in bytes are
+            // also their lengths in columns.
+            let len = if rest.starts_with("//") {
+                break;
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                tokens.push((Token::Ident(rest[..len].to_owned()), pos));
+                len
+            } else if c.is_ascii_digit() || c == '#' {
+                let len = rest[1..]
+                    .find(|c: char| !c.is_ascii_alphanumeric())
+                    .map_or(rest.len(), |len| len + 1);
+                tokens.push((Token::Number(number(&rest[..len], pos)?), pos));
+                len
+            } else if let Some(punct) = PUNCTS.iter().find(|p| rest.starts_with(**p)) {
+                tokens.push((Token::Punct(punct), pos));
+                punct.len()
+            } else {
+                return Err(Error::new(pos, format!("unexpected character `{c}`")));
+            };
+
+            rest = &rest[len..];
+            column += len;
+        }
+    }
+
+    let eof = Pos {
+        line: source.lines().count().max(1),
+        column: source.lines().last().map_or(0, |l| l.chars().count()) + 1,
+    };
+    tokens.push((Token::Eof, eof));
+
+    Ok(tokens)
+}
+
+pub(crate) fn number(text: &str, pos: Pos) -> Result<u32> {
+    let invalid = || Error::new(pos, format!("invalid number `{text}`"));
+
+    if let Some(hex) = text.strip_prefix('#') {
+        let v = u32::from_str_radix(hex, 16).map_err(|_| invalid())?;
+        return match hex.len() {
+            6 => Ok(v << 8 | 0xff),
+            8 => Ok(v),
+            _ => Err(Error::new(
+                pos,
+                format!("color `{text}` needs 6 or 8 hex digits"),
+            )),
+        };
+    }
+
+    if let Some(hex) = text.strip_prefix("0x") {
+        return u32::from_str_radix(hex, 16).map_err(|_| invalid());
+    }
+
+    text.parse().map_err(|_| invalid())
+}
+
+// Parsing
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    lets: Vec<(String, Pos, Expr)>,
+    next: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Number(u32),
+    Name(String, Pos),
+    Call(String, Pos, Vec<Expr>),
+    Channel(Box<Expr>, String, Pos),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+/// Binary operators from loosest to tightest binding. Each level is left-associative.
+const PRECEDENCE: &[&[&str]] = &[
+    &["||"],
+    &["&&"],
+    &["==", "!=", "<", "<=", ">", ">="],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["<<", ">>"],
+    &["+", "-", ".+", ".-"],
+    &["*", "/", "%", ".*"],
+];
+
+/// How deeply expressions can nest. Parsing, compiling, and even dropping an expression recurse
+/// once per level, so this keeps any source from running out of stack.
+const MAX_DEPTH: usize = 256;
+
+struct Parser {
+    tokens: Vec<(Token, Pos)>,
+    i: usize,
+
+    /// How many levels of [`MAX_DEPTH`] the expression being parsed has used so far.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.i].0
+    }
+
+    fn pos(&self) -> Pos {
+        self.tokens[self.i].1
+    }
+
+    fn bump(&mut self) -> (Token, Pos) {
+        let token = self.tokens[self.i].clone();
+        if token.0 != Token::Eof {
+            self.i += 1;
+        }
+        token
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Ident(name) if name == keyword)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if self.peek() == &Token::Punct(punct_str(punct)) {
+            self.i += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<()> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{punct}`")))
+        }
+    }
+
+    /// Goes one level deeper into an expression, failing if that's past [`MAX_DEPTH`].
+    fn nest(&mut self) -> Result<()> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::new(
+                self.pos(),
+                format!("expression nests more than {MAX_DEPTH} levels deep"),
+            ));
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn unexpected(&self, expected: &str) -> Error {
+        let found = match self.peek() {
+            Token::Ident(name) => format!("`{name}`"),
+            Token::Number(n) => format!("`{n}`"),
+            Token::Punct(p) => format!("`{p}`"),
+            Token::Eof => String::from("the end of the rule"),
+        };
+        Error::new(self.pos(), format!("expected {expected}, found {found}"))
+    }
+
+    fn rule(mut self) -> Result<Rule> {
+        let mut lets = Vec::new();
+
+        loop {
+            if self.at_keyword("let") {
+                self.bump();
+
+                let pos = self.pos();
+                let Token::Ident(name) = self.bump().0 else {
+                    return Err(Error::new(pos, "expected a name after `let`"));
+                };
+
+                self.expect("=")?;
+                let value = self.expr()?;
+                self.eat(";");
+
+                lets.push((name, pos, value));
+            } else if self.at_keyword("next") {
+                self.bump();
+                self.expect("=")?;
+                let next = self.expr()?;
+                self.eat(";");
+
+                if self.peek() != &Token::Eof {
+                    return Err(self.unexpected("the end of the rule after `next`"));
+                }
+
+                return Ok(Rule { lets, next });
+            } else if self.peek() == &Token::Eof {
+                return Err(Error::new(self.pos(), "missing `next = ...`"));
+            } else {
+                return Err(self.unexpected("`let` or `next`"));
+            }
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        self.binary(0)
+    }
+
+    /// Parses operators that bind at least as tightly as `PRECEDENCE[level]`, climbing to tighter
+    /// levels only for the right-hand side of each operator so nesting stays shallow.
+    fn binary(&mut self, level: usize) -> Result<Expr> {
+        // Each operator in a chain wraps everything before it, so the chain counts toward the
+        // depth even though it's parsed in a loop.
+        let depth = self.depth;
+
+        let mut lhs = self.unary()?;
+        while let Some((op_level, op)) = self.binary_op().filter(|(l, _)| *l >= level) {
+            self.nest()?;
+            self.bump();
+            let rhs = self.binary(op_level + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        self.depth = depth;
+        Ok(lhs)
+    }
+
+    /// The binary operator at the current token, along with its level in [`PRECEDENCE`].
+    fn binary_op(&self) -> Option<(usize, &'static str)> {
+        let Token::Punct(punct) = self.peek() else {
+            return None;
+        };
+
+        PRECEDENCE
+            .iter()
+            .enumerate()
+            .find_map(|(level, ops)| ops.contains(punct).then_some((level, *punct)))
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        for op in ["-", "~", "!"] {
+            if self.peek() == &Token::Punct(punct_str(op)) {
+                self.nest()?;
+                self.bump();
+                let operand = self.unary()?;
+                self.depth -= 1;
+                return Ok(Expr::Unary(punct_str(op), Box::new(operand)));
+            }
+        }
+
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr> {
+        let depth = self.depth;
+        let mut expr = self.primary()?;
+
+        while self.eat(".") {
+            self.nest()?;
+            let pos = self.pos();
+            let Token::Ident(channel) = self.bump().0 else {
+                return Err(Error::new(
+                    pos,
+                    "expected a channel (r, g, b, or a) after `.`",
+                ));
+            };
+            expr = Expr::Channel(Box::new(expr), channel, pos);
+        }
+
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let pos = self.pos();
+
+        match self.peek().clone() {
+            Token::Number(n) => {
+                self.bump();
+                Ok(Expr::Number(n))
+            }
+
+            Token::Ident(name) if name == "if" => {
+                self.bump();
+                self.if_rest()
+            }
+
+            Token::Ident(name) if KEYWORDS.contains(&name.as_str()) => {
+                Err(self.unexpected("a value"))
+            }
+
+            Token::Ident(name) => {
+                self.bump();
+
+                if !self.eat("(") {
+                    return Ok(Expr::Name(name, pos));
+                }
+
+                self.nest()?;
+                let mut args = Vec::new();
+                if !self.eat(")") {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.eat(")") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                self.depth -= 1;
+
+                Ok(Expr::Call(name, pos, args))
+            }
+
+            Token::Punct("(") => {
+                self.nest()?;
+                self.bump();
+                let expr = self.expr()?;
+                self.expect(")")?;
+                self.depth -= 1;
+                Ok(expr)
+            }
+
+            _ => Err(self.unexpected("a value")),
+        }
+    }
+
+    /// Parses the rest of an `if` expression after the keyword.
+    fn if_rest(&mut self) -> Result<Expr> {
+        self.nest()?;
+        let cond = self.expr()?;
+
+        self.expect("{")?;
+        let then = self.expr()?;
+        self.expect("}")?;
+
+        if !self.at_keyword("else") {
+            return Err(self.unexpected("`else` (every `if` needs one)"));
+        }
+        self.bump();
+
+        let otherwise = if self.at_keyword("if") {
+            self.bump();
+            self.if_rest()?
+        } else {
+            self.expect("{")?;
+            let otherwise = self.expr()?;
+            self.expect("}")?;
+            otherwise
+        };
+
+        self.depth -= 1;
+        Ok(Expr::If(
+            Box::new(cond),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+}
+
+/// Finds the `'static` version of a punctuation string, so tokens can be compared by value.
+fn punct_str(punct: &str) -> &'static str {
+    PUNCTS
+        .iter()
+        .find(|p| **p == punct)
+        .expect("known punctuation")
+}
+
+// Code generation
+
+fn is_param(name: &str) -> bool {
+    let b = name.as_bytes();
+    b.len() == 3 && b[0] == b'p' && (b'0'..=b'2').contains(&b[1]) && (b'0'..=b'2').contains(&b[2])
+}
+
+#[derive(Default)]
+struct Compiler {
+    imports: BTreeSet<String>,
+    locals: Vec<String>,
+    code: String,
+}
+
+impl Compiler {
+    fn rule(mut self, rule: &Rule) -> Result<String> {
+        for (name, pos, value) in &rule.lets {
+            let taken = if KEYWORDS.contains(&name.as_str()) || is_param(name) {
+                Some("a built-in name")
+            } else if host::arity(name).is_some() {
+                Some("a host function")
+            } else if self.locals.contains(name) {
+                Some("already defined")
+            } else {
+                None
+            };
+
+            if let Some(reason) = taken {
+                return Err(Error::new(*pos, format!("can't use `{name}`: {reason}")));
+            }
+
+            self.expr(value)?;
+            self.emit(&format!("local.set $v_{name}"));
+            self.locals.push(name.clone());
+        }
+
+        self.expr(&rule.next)?;
+
+        let mut wat = String::from("(module\n");
+        for name in &self.imports {
+            let params = "i32 ".repeat(host::arity(name).expect("checked when called"));
+            writeln!(
+                wat,
+                "  (import \"host\" \"{name}\" (func ${name} (param {params}) (result i32)))"
+            )
+            .unwrap();
+        }
+
+        wat.push_str("  (func (export \"next\")\n");
+        wat.push_str("    (param $p00 i32) (param $p01 i32) (param $p02 i32)\n");
+        wat.push_str("    (param $p10 i32) (param $p11 i32) (param $p12 i32)\n");
+        wat.push_str("    (param $p20 i32) (param $p21 i32) (param $p22 i32)\n");
+        wat.push_str("    (result i32)\n");
+        for name in &self.locals {
+            writeln!(wat, "    (local $v_{name} i32)").unwrap();
+        }
+        wat.push_str(&self.code);
+        wat.push_str("  )\n)\n");
+
+        Ok(wat)
+    }
+
+    fn emit(&mut self, instruction: &str) {
+        writeln!(self.code, "    {instruction}").unwrap();
+    }
+
+    fn call(&mut self, name: &str) {
+        self.imports.insert(name.to_owned());
+        self.emit(&format!("call ${name}"));
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<()> {
+        match expr {
+            Expr::Number(n) => self.emit(&format!("i32.const {}", *n as i32)),
+
+            Expr::Name(name, pos) => {
+                if self.locals.contains(name) {
+                    self.emit(&format!("local.get $v_{name}"));
+                } else if is_param(name) {
+                    self.emit(&format!("local.get ${name}"));
+                } else if host::arity(name) == Some(0) {
+                    self.call(name);
+                } else if host::arity(name).is_some() {
+                    return Err(Error::new(
+                        *pos,
+                        format!("`{name}` is a function, so it needs arguments"),
+                    ));
+                } else {
+                    return Err(Error::new(*pos, format!("unknown name `{name}`")));
+                }
+            }
+
+            Expr::Call(name, pos, args) => {
+                let Some(arity) = host::arity(name) else {
+                    return Err(Error::new(*pos, format!("unknown function `{name}`")));
+                };
+
+                if args.len() != arity {
+                    return Err(Error::new(
+                        *pos,
+                        format!("`{name}` takes {arity} arguments, but got {}", args.len()),
+                    ));
+                }
+
+                for arg in args {
+                    self.expr(arg)?;
+                }
+                self.call(name);
+            }
+
+            Expr::Channel(expr, channel, pos) => {
+                let func = match channel.as_str() {
+                    "r" => "red",
+                    "g" => "green",
+                    "b" => "blue",
+                    "a" => "alpha",
+                    _ => {
+                        return Err(Error::new(
+                            *pos,
+                            format!("unknown channel `{channel}` (expected r, g, b, or a)"),
+                        ))
+                    }
+                };
+
+                self.expr(expr)?;
+                self.call(func);
+            }
+
+            Expr::Unary(op, operand) => match *op {
+                "-" => {
+                    self.emit("i32.const 0");
+                    self.expr(operand)?;
+                    self.emit("i32.sub");
+                }
+                "~" => {
+                    self.expr(operand)?;
+                    self.emit("i32.const -1");
+                    self.emit("i32.xor");
+                }
+                "!" => {
+                    self.expr(operand)?;
+                    self.emit("i32.eqz");
+                }
+                _ => unreachable!("unknown unary operator {op}"),
+            },
+
+            Expr::Binary("&&", lhs, rhs) => {
+                self.expr(lhs)?;
+                self.emit("if (result i32)");
+                self.expr(rhs)?;
+                self.emit("i32.const 0");
+                self.emit("i32.ne");
+                self.emit("else");
+                self.emit("i32.const 0");
+                self.emit("end");
+            }
+
+            Expr::Binary("||", lhs, rhs) => {
+                self.expr(lhs)?;
+                self.emit("if (result i32)");
+                self.emit("i32.const 1");
+                self.emit("else");
+                self.expr(rhs)?;
+                self.emit("i32.const 0");
+                self.emit("i32.ne");
+                self.emit("end");
+            }
+
+            Expr::Binary(op, lhs, rhs) => {
+                self.expr(lhs)?;
+                self.expr(rhs)?;
+
+                match *op {
+                    ".+" => self.call("rgba_add_sat"),
+                    ".-" => self.call("rgba_sub_sat"),
+                    ".*" => self.call("rgba_mul"),
+                    op => {
+                        let instruction = match op {
+                            "+" => "i32.add",
+                            "-" => "i32.sub",
+                            "*" => "i32.mul",
+                            "/" => "i32.div_u",
+                            "%" => "i32.rem_u",
+                            "&" => "i32.and",
+                            "|" => "i32.or",
+                            "^" => "i32.xor",
+                            "<<" => "i32.shl",
+                            ">>" => "i32.shr_u",
+                            "==" => "i32.eq",
+                            "!=" => "i32.ne",
+                            "<" => "i32.lt_u",
+                            "<=" => "i32.le_u",
+                            ">" => "i32.gt_u",
+                            ">=" => "i32.ge_u",
+                            _ => unreachable!("unknown binary operator {op}"),
+                        };
+                        self.emit(instruction);
+                    }
+                }
+            }
+
+            Expr::If(cond, then, otherwise) => {
+                self.expr(cond)?;
+                self.emit("if (result i32)");
+                self.expr(then)?;
+                self.emit("else");
+                self.expr(otherwise)?;
+                self.emit("end");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Interpreter, Position};
+
+    /// Compiles the rule and runs it once on the given neighborhood.
+    fn eval(source: &str, neighbors: [u32; 9]) -> u32 {
+        let wat = compile(source).unwrap_or_else(|err| panic!("{err}\n{source}"));
+        let binary = wat::parse_str(&wat).unwrap_or_else(|err| panic!("{err}\n{wat}"));
+
+        let [a, b, c, d, e, f, g, h, i] = neighbors;
+        Interpreter::new(&binary, 0)
+            .unwrap()
+            .eval(Position::default(), (a, b, c, d, e, f, g, h, i))
+            .unwrap()
+    }
+
+    fn error(source: &str) -> String {
+        compile(source).unwrap_err().to_string()
+    }
+
+    const N: [u32; 9] = [1, 2, 4, 8, 16, 32, 64, 128, 256];
+
+    #[test]
+    fn example_matches_default_module() {
+        let binary = wat::parse_str(compile(EXAMPLE).unwrap()).unwrap();
+        let default = crate::Module::default().binary;
+
+        for seed in 0..3 {
+            let mut a = crate::Runner::new(&binary, seed).unwrap();
+            let mut b = crate::Runner::new(&default, seed).unwrap();
+            for _ in 0..3 {
+                a.step().unwrap();
+                b.step().unwrap();
+            }
+            assert_eq!(a.next(), b.next());
+        }
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(eval("next = p00 + p01 * p02", N), 1 + 2 * 4);
+        assert_eq!(eval("next = (p00 + p01) * p02", N), (1 + 2) * 4);
+        assert_eq!(eval("next = p22 / p02 % 5", N), 256 / 4 % 5);
+        assert_eq!(eval("next = p22 >> 2 | p00 << 1", N), 64 | 2);
+        assert_eq!(eval("next = p11 & 0x30 ^ 1", N), 16 ^ 1);
+        assert_eq!(eval("next = -p00", N), u32::MAX);
+        assert_eq!(eval("next = ~0", N), u32::MAX);
+        assert_eq!(eval("next = 0 - 1 > 5", N), 1);
+    }
+
+    #[test]
+    fn logic_and_conditionals() {
+        assert_eq!(eval("next = p00 < p01 && p01 < p02", N), 1);
+        assert_eq!(eval("next = p00 > p01 || !p11", N), 0);
+        assert_eq!(eval("next = p00 == 1 || 1 / 0", N), 1, "short-circuits");
+
+        let source = "next = if p11 > 100 { 1 } else if p11 > 10 { 2 } else { 3 }";
+        assert_eq!(eval(source, N), 2);
+    }
+
+    #[test]
+    fn colors_and_channels() {
+        assert_eq!(eval("next = #ff8000", N), 0xff8000ff);
+        assert_eq!(eval("next = #ff800080", N), 0xff800080);
+        assert_eq!(eval("next = #12345678.g", N), 0x34);
+        assert_eq!(eval("next = #10203040 .+ #f0f0f0f0", N), 0xffffffff);
+        assert_eq!(eval("next = #10203040 .- #20202020", N), 0x00001020);
+        assert_eq!(eval("next = #ff8040ff .* #80808080", N), 0x80402080);
+    }
+
+    #[test]
+    fn lets_and_host_functions() {
+        let source = "
+            // Comments are ignored.
+            let total = p00 + p01; let doubled = total * 2
+            next = rgba(doubled, grid_size, tick(), 255) // So is this one.
+        ";
+        assert_eq!(eval(source, N), 0x06100000 | 0xff);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error(""), "line 1, column 1: missing `next = ...`");
+        assert_eq!(
+            error("let x = 1\nnext = y"),
+            "line 2, column 8: unknown name `y`"
+        );
+        assert_eq!(
+            error("next = rgba(1, 2)"),
+            "line 1, column 8: `rgba` takes 4 arguments, but got 2"
+        );
+        assert_eq!(
+            error("next = nope(1)"),
+            "line 1, column 8: unknown function `nope`"
+        );
+        assert_eq!(
+            error("next = p00.x"),
+            "line 1, column 12: unknown channel `x` (expected r, g, b, or a)"
+        );
+        assert_eq!(
+            error("next = (p00 + 1"),
+            "line 1, column 16: expected `)`, found the end of the rule"
+        );
+        assert_eq!(
+            error("next = if p00 { 1 }"),
+            "line 1, column 20: expected `else` (every `if` needs one), found the end of the rule"
+        );
+        assert_eq!(
+            error("let p11 = 1\nnext = 0"),
+            "line 1, column 5: can't use `p11`: a built-in name"
+        );
+        assert_eq!(
+            error("let a = 1\nlet a = 2\nnext = a"),
+            "line 2, column 5: can't use `a`: already defined"
+        );
+        assert_eq!(
+            error("next = 1\nnext = 2"),
+            "line 2, column 1: expected the end of the rule after `next`, found `next`"
+        );
+        assert_eq!(
+            error("next = #fff"),
+            "line 1, column 8: color `#fff` needs 6 or 8 hex digits"
+        );
+        assert_eq!(
+            error("next = 1 @ 2"),
+            "line 1, column 10: unexpected character `@`"
+        );
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let ok = |depth: usize| {
+            [
+                format!("next = {}1", "-".repeat(depth)),
+                format!("next = {}1{}", "(".repeat(depth), ")".repeat(depth)),
+                format!("next = 1{}", " + 1".repeat(depth)),
+                format!("next = 1{}", ".r".repeat(depth)),
+            ]
+        };
+
+        for source in ok(MAX_DEPTH) {
+            eval(&source, N);
+        }
+
+        for source in ok(MAX_DEPTH + 1) {
+            let err = error(&source);
+            assert!(
+                err.ends_with("expression nests more than 256 levels deep"),
+                "{err}"
+            );
+        }
+
+        // Far past the limit, this still fails cleanly instead of overflowing the stack.
+        let source = format!("next = {}1", "-".repeat(20_000));
+        assert_eq!(
+            error(&source),
+            "line 1, column 264: expression nests more than 256 levels deep"
+        );
+    }
+
+    #[test]
+    fn long_lines_lex_quickly() {
+        // Lexing used to copy the rest of the line for every character, which took seconds here.
+        let terms = 50_000;
+        let source = format!("next = 0{}", " + 0x1".repeat(terms));
+        let tokens = lex(&source).unwrap();
+        assert_eq!(
+            tokens.len(),
+            4 + 2 * terms,
+            "next = 0, the terms, and the end"
+        );
+    }
+}
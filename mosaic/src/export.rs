@@ -0,0 +1,280 @@
+//! Image encoders for saving what a simulation produces.
+//!
+//! These only depend on [`Blocks`] and [`Runner`], so they work the same in the browser, on the
+//! command line, and in tests.
+
+use crate::{Blocks, EdgeMode, Initial, InstantiateError, Rgba, Runner, StepError, GRID_SIZE};
+
+/// The largest number of image pixels per block (in each direction).
+pub const MAX_SCALE: u32 = 64;
+
+/// The longest recording to allow, since GIFs are encoded all at once in memory.
+pub const MAX_TICKS: u64 = 1000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("scale must be between 1 and {MAX_SCALE}, got {0}")]
+    Scale(u32),
+
+    #[error("can record at most {MAX_TICKS} ticks, got {0}")]
+    Ticks(u64),
+
+    #[error(transparent)]
+    Instantiate(#[from] InstantiateError),
+
+    #[error(transparent)]
+    Step(#[from] StepError),
+
+    #[error("PNG encoding failed: {0}")]
+    Png(#[from] png::EncodingError),
+
+    #[error("GIF encoding failed: {0}")]
+    Gif(#[from] gif::EncodingError),
+}
+
+/// Encodes the blocks as a PNG with `scale` by `scale` pixels per block, keeping their alpha.
+pub fn png(blocks: &Blocks, scale: u32) -> Result<Vec<u8>, ExportError> {
+    let size = image_size(scale)?;
+    let pixels = scaled_rgba8(blocks, scale);
+
+    let mut out = Vec::new();
+
+    let mut encoder = png::Encoder::new(&mut out, size, size);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&pixels)?;
+    writer.finish()?;
+
+    Ok(out)
+}
+
+/// Options for recording an animated GIF.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    pub seed: u64,
+    pub edges: EdgeMode,
+    pub initial: Initial,
+
+    /// How many ticks to run. The GIF has one more frame than this for the initial state.
+    pub ticks: u64,
+
+    pub scale: u32,
+    pub frame_millis: u32,
+}
+
+/// Runs the module from its initial state and encodes every tick as a looping GIF.
+///
+/// GIFs can't store partial transparency, so each frame is flattened the way the page shows it:
+/// the current tick drawn over the previous one, both over a white background.
+pub fn gif(update: &[u8], recording: Recording) -> Result<Vec<u8>, ExportError> {
+    let Recording {
+        seed,
+        edges,
+        initial,
+        ticks,
+        scale,
+        frame_millis,
+    } = recording;
+
+    if ticks > MAX_TICKS {
+        return Err(ExportError::Ticks(ticks));
+    }
+
+    let size = image_size(scale)? as u16;
+
+    let mut runner = Runner::new(update, seed)?
+        .with_edges(edges)
+        .with_initial(&initial);
+
+    let mut out = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut out, size, size, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        // GIF delays are in hundredths of a second.
+        let delay = (frame_millis / 10).min(u32::from(u16::MAX)) as u16;
+
+        loop {
+            let flat = flatten(runner.prev(), runner.next());
+            let mut pixels = scaled_rgba8(&flat, scale);
+            let mut frame = gif::Frame::from_rgba_speed(size, size, &mut pixels, 10);
+            frame.delay = delay;
+
+            encoder.write_frame(&frame)?;
+
+            if runner.tick() == ticks {
+                break;
+            }
+            runner.step()?;
+        }
+    }
+
+    Ok(out)
+}
+
+fn image_size(scale: u32) -> Result<u32, ExportError> {
+    if !(1..=MAX_SCALE).contains(&scale) {
+        return Err(ExportError::Scale(scale));
+    }
+
+    Ok(GRID_SIZE as u32 * scale)
+}
+
+/// Encodes the blocks as a binary PPM (P6) with `scale` by `scale` pixels per block.
+///
+/// PPM has no alpha channel, so this ignores it. Use [`flatten`] first to keep the colors the page
+/// would show.
+pub fn ppm(blocks: &Blocks, scale: u32) -> Result<Vec<u8>, ExportError> {
+    let size = image_size(scale)?;
+    let pixels = scaled_rgba8(blocks, scale);
+
+    let mut out = format!("P6\n{size} {size}\n255\n").into_bytes();
+    for rgba in pixels.chunks_exact(4) {
+        out.extend_from_slice(&rgba[..3]);
+    }
+
+    Ok(out)
+}
+
+/// Draws `next` over `prev` over white, leaving every block opaque.
+pub fn flatten(prev: &Blocks, next: &Blocks) -> Blocks {
+    let mut flat = Blocks::default();
+
+    for r in 0..GRID_SIZE {
+        for c in 0..GRID_SIZE {
+            flat[(r, c)] = next[(r, c)].over(prev[(r, c)].over(Rgba::WHITE));
+        }
+    }
+
+    flat
+}
+
+/// Like [`Blocks::rgba8`], but repeats each block `scale` times in each direction.
+fn scaled_rgba8(blocks: &Blocks, scale: u32) -> Vec<u8> {
+    let scale = scale as usize;
+    let size = GRID_SIZE * scale;
+    let mut pixels = Vec::with_capacity(size * size * 4);
+
+    for y in 0..size {
+        for x in 0..size {
+            let rgba = blocks[(y / scale, x / scale)];
+            pixels.extend(u32::from(rgba).to_be_bytes());
+        }
+    }
+
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Module;
+
+    fn decode_png(bytes: &[u8]) -> (png::OutputInfo, Vec<u8>) {
+        let decoder = png::Decoder::new(bytes);
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        buf.truncate(info.buffer_size());
+        (info, buf)
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let blocks = Blocks::from_seed(7);
+
+        let (info, pixels) = decode_png(&png(&blocks, 1).unwrap());
+
+        assert_eq!((info.width, info.height), (16, 16));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        assert_eq!(pixels, blocks.rgba8());
+    }
+
+    #[test]
+    fn png_scales_blocks() {
+        let blocks = Blocks::from_seed(7);
+        let scale = 3;
+
+        let (info, pixels) = decode_png(&png(&blocks, scale).unwrap());
+        assert_eq!((info.width, info.height), (48, 48));
+
+        let pixel = |x: usize, y: usize| {
+            let i = (y * 48 + x) * 4;
+            u32::from_be_bytes(pixels[i..i + 4].try_into().unwrap())
+        };
+
+        for (x, y) in [(0, 0), (2, 2), (3, 0), (47, 47), (20, 31)] {
+            let block = blocks[(y / 3, x / 3)];
+            assert_eq!(pixel(x, y), u32::from(block), "pixel ({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn png_rejects_bad_scale() {
+        let blocks = Blocks::default();
+        assert!(matches!(png(&blocks, 0), Err(ExportError::Scale(0))));
+        assert!(matches!(png(&blocks, 65), Err(ExportError::Scale(65))));
+    }
+
+    #[test]
+    fn gif_records_every_tick() {
+        let module = Module::default();
+        let recording = Recording {
+            seed: 42,
+            edges: EdgeMode::Wrap,
+            initial: Initial::Noise,
+            ticks: 5,
+            scale: 2,
+            frame_millis: 250,
+        };
+
+        let bytes = gif(&module.binary, recording).unwrap();
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(&bytes[..]).unwrap();
+
+        assert_eq!((decoder.width(), decoder.height()), (32, 32));
+
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 25);
+            assert!(frame.buffer.chunks(4).all(|px| px[3] == 255));
+            frames += 1;
+        }
+
+        assert_eq!(frames, 6);
+    }
+
+    #[test]
+    fn gif_reports_broken_modules() {
+        let trap = Module::new(String::from(
+            r#"(module (func (export "next")
+                (param i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+                unreachable))"#,
+        ))
+        .unwrap();
+
+        let recording = Recording {
+            seed: 1,
+            edges: EdgeMode::Clamp,
+            initial: Initial::Center,
+            ticks: 3,
+            scale: 1,
+            frame_millis: 100,
+        };
+
+        let err = gif(&trap.binary, recording).unwrap_err();
+        assert!(matches!(
+            err,
+            ExportError::Step(StepError {
+                tick: 1,
+                row: 0,
+                col: 0,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,549 @@
+//! Host functions that rule modules can import from the `host` namespace.
+//!
+//! Every color is a packed RGBA `i32` (the same format `next` receives and returns). Channel
+//! arguments and results are `i32`s in `0..=255`, and out-of-range channel inputs are clamped.
+
+use wasmi::Caller;
+
+use crate::{Position, Rgba, GRID_SIZE, MAX_MEMORY_PAGES, MAX_TABLE_ELEMENTS};
+
+/// Per-store data that host functions can read.
+#[derive(Debug, Clone, Default)]
+pub struct HostState {
+    pub seed: u64,
+    pub position: Position,
+
+    /// How many random numbers the current cell has drawn so far.
+    draws: u64,
+
+    /// How much memory and table space the module can use. Host functions don't read this, but
+    /// the store's limiter needs somewhere to keep it.
+    pub(crate) limits: wasmi::StoreLimits,
+}
+
+impl HostState {
+    pub fn new(seed: u64) -> Self {
+        let limits = wasmi::StoreLimitsBuilder::new()
+            .memory_size(MAX_MEMORY_PAGES * WASM_PAGE_SIZE)
+            .table_elements(MAX_TABLE_ELEMENTS)
+            .memories(1)
+            .tables(1)
+            .build();
+
+        Self {
+            seed,
+            limits,
+            ..Default::default()
+        }
+    }
+
+    pub fn move_to(&mut self, position: Position) {
+        self.position = position;
+        self.draws = 0;
+    }
+}
+
+const WASM_PAGE_SIZE: usize = 64 * 1024;
+
+/// Signatures and descriptions of all host functions, for showing to rule authors.
+pub const DOCS: &[(&str, &str)] = &[
+    (
+        "i32_add_sat(a, b) -> i32",
+        "Unsigned addition that stops at the maximum value.",
+    ),
+    (
+        "i32_sub_sat(a, b) -> i32",
+        "Unsigned subtraction that stops at zero.",
+    ),
+    (
+        "rgba(r, g, b, a) -> i32",
+        "Packs four channels into a color.",
+    ),
+    ("red(c) -> i32", "The red channel of a color."),
+    ("green(c) -> i32", "The green channel of a color."),
+    ("blue(c) -> i32", "The blue channel of a color."),
+    ("alpha(c) -> i32", "The alpha channel of a color."),
+    (
+        "rgba_add_sat(a, b) -> i32",
+        "Adds each channel, stopping at 255.",
+    ),
+    (
+        "rgba_sub_sat(a, b) -> i32",
+        "Subtracts each channel, stopping at 0.",
+    ),
+    (
+        "rgba_mul(a, b) -> i32",
+        "Multiplies each channel, treating 255 as 1.0.",
+    ),
+    (
+        "rgba_blend(a, b, t) -> i32",
+        "Mixes from a (t = 0) to b (t = 255) in each channel.",
+    ),
+    (
+        "rgba_over(top, bottom) -> i32",
+        "Draws top over bottom using top's alpha.",
+    ),
+    (
+        "hsv(h, s, v) -> i32",
+        "An opaque color from hue (0-359), saturation, and value.",
+    ),
+    ("hue(c) -> i32", "The hue of a color (0-359)."),
+    ("saturation(c) -> i32", "The HSV saturation of a color."),
+    ("value(c) -> i32", "The HSV value (brightness) of a color."),
+    (
+        "random() -> i32",
+        "A random number that is the same for every run with this seed.",
+    ),
+    (
+        "random_below(n) -> i32",
+        "A random number in 0..n (or 0 if n is 0).",
+    ),
+    (
+        "tick() -> i32",
+        "The tick being computed (the first update is tick 1).",
+    ),
+    ("row() -> i32", "The row of the cell being computed."),
+    ("col() -> i32", "The column of the cell being computed."),
+    (
+        "grid_size() -> i32",
+        "The number of rows (and columns) in the grid.",
+    ),
+];
+
+/// The number of parameters a host function takes, or `None` if there's no such function.
+pub fn arity(name: &str) -> Option<usize> {
+    DOCS.iter().find_map(|(signature, _)| {
+        let (func, rest) = signature.split_once('(')?;
+        if func != name {
+            return None;
+        }
+
+        let (params, _) = rest.split_once(')')?;
+        Some(params.split(',').filter(|p| !p.trim().is_empty()).count())
+    })
+}
+
+pub fn bind(
+    store: &mut wasmi::Store<HostState>,
+    linker: &mut wasmi::Linker<HostState>,
+) -> Result<(), wasmi::errors::LinkerError> {
+    macro_rules! bind {
+        ($name:ident) => {{
+            let func = ::wasmi::Func::wrap(&mut *store, self::$name);
+            linker.define("host", stringify!($name), func)
+        }};
+    }
+
+    bind!(i32_add_sat)?;
+    bind!(i32_sub_sat)?;
+
+    bind!(rgba)?;
+    bind!(red)?;
+    bind!(green)?;
+    bind!(blue)?;
+    bind!(alpha)?;
+
+    bind!(rgba_add_sat)?;
+    bind!(rgba_sub_sat)?;
+    bind!(rgba_mul)?;
+    bind!(rgba_blend)?;
+    bind!(rgba_over)?;
+
+    bind!(hsv)?;
+    bind!(hue)?;
+    bind!(saturation)?;
+    bind!(value)?;
+
+    bind!(random)?;
+    bind!(random_below)?;
+
+    bind!(tick)?;
+    bind!(row)?;
+    bind!(col)?;
+    bind!(grid_size)?;
+
+    Ok(())
+}
+
+pub fn i32_add_sat(_: Caller<'_, HostState>, a: u32, b: u32) -> u32 {
+    a.saturating_add(b)
+}
+
+pub fn i32_sub_sat(_: Caller<'_, HostState>, a: u32, b: u32) -> u32 {
+    a.saturating_sub(b)
+}
+
+// Packing and unpacking
+
+pub fn rgba(_: Caller<'_, HostState>, r: u32, g: u32, b: u32, a: u32) -> u32 {
+    pack([channel(r), channel(g), channel(b), channel(a)])
+}
+
+pub fn red(_: Caller<'_, HostState>, c: u32) -> u32 {
+    unpack(c)[0].into()
+}
+
+pub fn green(_: Caller<'_, HostState>, c: u32) -> u32 {
+    unpack(c)[1].into()
+}
+
+pub fn blue(_: Caller<'_, HostState>, c: u32) -> u32 {
+    unpack(c)[2].into()
+}
+
+pub fn alpha(_: Caller<'_, HostState>, c: u32) -> u32 {
+    unpack(c)[3].into()
+}
+
+// Channel-wise arithmetic
+
+pub fn rgba_add_sat(_: Caller<'_, HostState>, a: u32, b: u32) -> u32 {
+    zip(a, b, u8::saturating_add)
+}
+
+pub fn rgba_sub_sat(_: Caller<'_, HostState>, a: u32, b: u32) -> u32 {
+    zip(a, b, u8::saturating_sub)
+}
+
+pub fn rgba_mul(_: Caller<'_, HostState>, a: u32, b: u32) -> u32 {
+    zip(a, b, mul_255)
+}
+
+pub fn rgba_blend(_: Caller<'_, HostState>, a: u32, b: u32, t: u32) -> u32 {
+    let t = channel(t);
+    zip(a, b, |x, y| {
+        mul_255(x, 255 - t).saturating_add(mul_255(y, t))
+    })
+}
+
+pub fn rgba_over(_: Caller<'_, HostState>, top: u32, bottom: u32) -> u32 {
+    Rgba::from(top).over(Rgba::from(bottom)).into()
+}
+
+// HSV conversion
+
+pub fn hsv(_: Caller<'_, HostState>, h: u32, s: u32, v: u32) -> u32 {
+    let [r, g, b] = hsv_to_rgb(h % 360, channel(s), channel(v));
+    pack([r, g, b, 255])
+}
+
+pub fn hue(_: Caller<'_, HostState>, c: u32) -> u32 {
+    rgb_to_hsv(c).0
+}
+
+pub fn saturation(_: Caller<'_, HostState>, c: u32) -> u32 {
+    rgb_to_hsv(c).1.into()
+}
+
+pub fn value(_: Caller<'_, HostState>, c: u32) -> u32 {
+    rgb_to_hsv(c).2.into()
+}
+
+// Randomness
+
+pub fn random(mut caller: Caller<'_, HostState>) -> u32 {
+    let state = caller.data_mut();
+    let draw = state.draws;
+    state.draws += 1;
+
+    let Position { tick, row, col } = state.position;
+
+    let mut hash = splitmix64(state.seed);
+    for v in [tick, row as u64, col as u64, draw] {
+        hash = splitmix64(hash ^ v);
+    }
+
+    // Keep the high bits, which are the best mixed.
+    (hash >> 32) as u32
+}
+
+pub fn random_below(caller: Caller<'_, HostState>, n: u32) -> u32 {
+    if n == 0 {
+        return 0;
+    }
+
+    // Multiply-shift instead of modulo to avoid biasing toward small numbers.
+    ((u64::from(random(caller)) * u64::from(n)) >> 32) as u32
+}
+
+// Simulation context
+
+pub fn tick(caller: Caller<'_, HostState>) -> u32 {
+    caller.data().position.tick as u32
+}
+
+pub fn row(caller: Caller<'_, HostState>) -> u32 {
+    caller.data().position.row as u32
+}
+
+pub fn col(caller: Caller<'_, HostState>) -> u32 {
+    caller.data().position.col as u32
+}
+
+pub fn grid_size(_: Caller<'_, HostState>) -> u32 {
+    GRID_SIZE as u32
+}
+
+// Helpers
+
+fn pack(channels: [u8; 4]) -> u32 {
+    u32::from_be_bytes(channels)
+}
+
+fn unpack(c: u32) -> [u8; 4] {
+    c.to_be_bytes()
+}
+
+fn channel(v: u32) -> u8 {
+    v.min(255) as u8
+}
+
+fn zip(a: u32, b: u32, f: impl Fn(u8, u8) -> u8) -> u32 {
+    let (a, b) = (unpack(a), unpack(b));
+    pack(std::array::from_fn(|i| f(a[i], b[i])))
+}
+
+/// Multiplies two channels as if they were fractions of 255, rounding to nearest.
+fn mul_255(a: u8, b: u8) -> u8 {
+    ((u32::from(a) * u32::from(b) + 127) / 255) as u8
+}
+
+fn hsv_to_rgb(h: u32, s: u8, v: u8) -> [u8; 3] {
+    let (s, v) = (u32::from(s), u32::from(v));
+
+    let sector = h / 60;
+    let f = (h % 60) * 255 / 60;
+
+    let p = (v * (255 - s) / 255) as u8;
+    let q = (v * (255 - s * f / 255) / 255) as u8;
+    let t = (v * (255 - s * (255 - f) / 255) / 255) as u8;
+    let v = v as u8;
+
+    match sector {
+        0 => [v, t, p],
+        1 => [q, v, p],
+        2 => [p, v, t],
+        3 => [p, q, v],
+        4 => [t, p, v],
+        _ => [v, p, q],
+    }
+}
+
+fn rgb_to_hsv(c: u32) -> (u32, u8, u8) {
+    let [r, g, b, _] = unpack(c);
+    let (r, g, b) = (i32::from(r), i32::from(g), i32::from(b));
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    if delta == 0 {
+        return (0, 0, max as u8);
+    }
+
+    let s = delta * 255 / max;
+
+    let h = if max == r {
+        60 * (g - b) / delta
+    } else if max == g {
+        120 + 60 * (b - r) / delta
+    } else {
+        240 + 60 * (r - g) / delta
+    };
+
+    (h.rem_euclid(360) as u32, s as u8, max as u8)
+}
+
+fn splitmix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Interpreter;
+
+    /// Builds an interpreter whose `next` returns `host.$name` applied to the first `arity`
+    /// neighborhood values.
+    fn call(name: &str, arity: usize, args: &[u32]) -> u32 {
+        let params = (0..9)
+            .map(|i| format!("(param $p{i} i32)"))
+            .collect::<String>();
+        let import_params = "i32 ".repeat(arity);
+        let gets = (0..arity)
+            .map(|i| format!("(local.get $p{i})"))
+            .collect::<String>();
+
+        let wat = format!(
+            r#"(module
+                (import "host" "{name}" (func $f (param {import_params}) (result i32)))
+                (func (export "next") {params} (result i32) (call $f {gets})))"#
+        );
+
+        let mut neighbors = [0; 9];
+        neighbors[..args.len()].copy_from_slice(args);
+        let [a, b, c, d, e, f, g, h, i] = neighbors;
+
+        let binary = wat::parse_str(wat).unwrap();
+        let mut interpreter = Interpreter::new(&binary, 0).unwrap();
+        interpreter
+            .eval(Position::default(), (a, b, c, d, e, f, g, h, i))
+            .unwrap()
+    }
+
+    #[test]
+    fn docs_match_bindings() {
+        for (signature, _) in DOCS {
+            let (name, _) = signature.split_once('(').unwrap();
+            let arity = arity(name).unwrap();
+
+            // Instantiating checks that the import's type matches the real function.
+            let wat = format!(
+                r#"(module
+                    (import "host" "{name}" (func $f (param {params}) (result i32)))
+                    (func (export "next") (param i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+                        (i32.const 0)))"#,
+                params = "i32 ".repeat(arity),
+            );
+
+            let binary = wat::parse_str(wat).unwrap();
+            assert!(Interpreter::new(&binary, 0).is_ok(), "{signature}");
+        }
+
+        assert_eq!(arity("rgba"), Some(4));
+        assert_eq!(arity("tick"), Some(0));
+        assert_eq!(arity("nope"), None);
+    }
+
+    #[test]
+    fn pack_unpack() {
+        assert_eq!(call("rgba", 4, &[0x12, 0x34, 0x56, 0x78]), 0x12345678);
+        assert_eq!(call("rgba", 4, &[300, 0, 0, 255]), 0xFF0000FF);
+
+        assert_eq!(call("red", 1, &[0x12345678]), 0x12);
+        assert_eq!(call("green", 1, &[0x12345678]), 0x34);
+        assert_eq!(call("blue", 1, &[0x12345678]), 0x56);
+        assert_eq!(call("alpha", 1, &[0x12345678]), 0x78);
+    }
+
+    #[test]
+    fn saturating_arithmetic() {
+        assert_eq!(call("i32_add_sat", 2, &[u32::MAX, 1]), u32::MAX);
+        assert_eq!(call("i32_sub_sat", 2, &[0, 1]), 0);
+
+        assert_eq!(
+            call("rgba_add_sat", 2, &[0xF0100080, 0x20200080]),
+            0xFF3000FF
+        );
+        assert_eq!(
+            call("rgba_sub_sat", 2, &[0x10F00080, 0x20200080]),
+            0x00D00000
+        );
+        assert_eq!(call("rgba_mul", 2, &[0xFF80FF00, 0x80FF00FF]), 0x80800000);
+    }
+
+    #[test]
+    fn blending() {
+        let (a, b) = (0x000000FF, 0xFFFFFFFF);
+        assert_eq!(call("rgba_blend", 3, &[a, b, 0]), a);
+        assert_eq!(call("rgba_blend", 3, &[a, b, 255]), b);
+        assert_eq!(call("rgba_blend", 3, &[a, b, 128]), 0x808080FF);
+
+        assert_eq!(call("rgba_over", 2, &[0xFF0000FF, 0x00FF00FF]), 0xFF0000FF);
+        assert_eq!(call("rgba_over", 2, &[0xFF000000, 0x00FF00FF]), 0x00FF00FF);
+        assert_eq!(call("rgba_over", 2, &[0xFF000080, 0x0000FFFF]), 0x80007FFF);
+    }
+
+    #[test]
+    fn hsv_conversion() {
+        assert_eq!(call("hsv", 3, &[0, 255, 255]), 0xFF0000FF);
+        assert_eq!(call("hsv", 3, &[120, 255, 255]), 0x00FF00FF);
+        assert_eq!(call("hsv", 3, &[240, 255, 255]), 0x0000FFFF);
+        assert_eq!(call("hsv", 3, &[600, 0, 128]), 0x808080FF);
+
+        assert_eq!(call("hue", 1, &[0x00FF00FF]), 120);
+        assert_eq!(call("hue", 1, &[0xFF00FFFF]), 300);
+        assert_eq!(call("saturation", 1, &[0xFF8080FF]), 127);
+        assert_eq!(call("value", 1, &[0x204060FF]), 0x60);
+
+        for h in (0..360).step_by(15) {
+            let c = call("hsv", 3, &[h, 255, 255]);
+            let back = call("hue", 1, &[c]);
+            assert!(back.abs_diff(h) <= 1, "hue {h} came back as {back}");
+        }
+    }
+
+    #[test]
+    fn random_is_deterministic() {
+        let wat = r#"(module
+            (import "host" "random" (func $random (result i32)))
+            (func (export "next")
+                (param i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+                (i32.xor (call $random) (i32.rotl (call $random) (i32.const 7)))))"#;
+        let binary = wat::parse_str(wat).unwrap();
+        let neighbors = (0, 0, 0, 0, 0, 0, 0, 0, 0);
+
+        let run = |seed, position| {
+            let mut interpreter = Interpreter::new(&binary, seed).unwrap();
+            interpreter.eval(position, neighbors).unwrap()
+        };
+
+        let here = Position {
+            tick: 3,
+            row: 4,
+            col: 5,
+        };
+        let there = Position { col: 6, ..here };
+        let later = Position { tick: 4, ..here };
+
+        assert_eq!(run(1, here), run(1, here));
+        assert_ne!(run(1, here), run(2, here));
+        assert_ne!(run(1, here), run(1, there));
+        assert_ne!(run(1, here), run(1, later));
+
+        // Each cell starts its sequence over, no matter what was drawn before.
+        let mut interpreter = Interpreter::new(&binary, 1).unwrap();
+        let first = interpreter.eval(here, neighbors).unwrap();
+        let again = interpreter.eval(here, neighbors).unwrap();
+        assert_eq!(first, again);
+    }
+
+    #[test]
+    fn random_below_stays_in_range() {
+        for n in [0, 1, 2, 7, 255] {
+            let v = call("random_below", 1, &[n]);
+            assert!(v < n.max(1), "random_below({n}) returned {v}");
+        }
+    }
+
+    #[test]
+    fn context() {
+        let wat = r#"(module
+            (import "host" "tick" (func $tick (result i32)))
+            (import "host" "row" (func $row (result i32)))
+            (import "host" "col" (func $col (result i32)))
+            (import "host" "grid_size" (func $grid_size (result i32)))
+            (func (export "next")
+                (param i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+                (call $tick)
+                (i32.shl (call $row) (i32.const 8)) i32.or
+                (i32.shl (call $col) (i32.const 16)) i32.or
+                (i32.shl (call $grid_size) (i32.const 24)) i32.or))"#;
+        let binary = wat::parse_str(wat).unwrap();
+        let mut interpreter = Interpreter::new(&binary, 0).unwrap();
+
+        let position = Position {
+            tick: 9,
+            row: 2,
+            col: 15,
+        };
+        let v = interpreter
+            .eval(position, (0, 0, 0, 0, 0, 0, 0, 0, 0))
+            .unwrap();
+
+        assert_eq!(v, 0x100F0209);
+    }
+}
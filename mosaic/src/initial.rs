@@ -0,0 +1,282 @@
+//! Ways to fill the grid before the first tick.
+
+use base64::Engine as _;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+use crate::share::BASE64_URL_SAFE_LENIENT;
+use crate::{Blocks, Rgba, GRID_SIZE};
+
+/// The density for [`Initial::Sparse`] when nothing else is chosen.
+pub const DEFAULT_DENSITY: u8 = 10;
+
+/// How to fill the grid before the first tick. Everything but [`Initial::Image`] is random, so
+/// the seed picks which version of the pattern to use.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Initial {
+    /// Every cell is a random color.
+    #[default]
+    Noise,
+
+    /// One random opaque color in the middle, with every other cell cleared.
+    Center,
+
+    /// Random colors mirrored across both axes.
+    Symmetric,
+
+    /// A random color in roughly `density` percent of the cells, with the rest cleared.
+    Sparse { density: u8 },
+
+    /// A smooth blend between two random opaque colors from the top-left to the bottom-right.
+    Gradient,
+
+    /// A picture, already scaled to the grid.
+    Image(Box<Blocks>),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParseInitialError {
+    #[error("unknown initial state {0:?}")]
+    Unknown(String),
+
+    #[error("invalid density {0:?} (expected a percentage from 0 to 100)")]
+    Density(String),
+
+    #[error("invalid image data")]
+    Image,
+}
+
+impl Initial {
+    /// The generators that don't need anything besides the seed.
+    pub const PATTERNS: [Initial; 5] = [
+        Initial::Noise,
+        Initial::Center,
+        Initial::Symmetric,
+        Initial::Sparse {
+            density: DEFAULT_DENSITY,
+        },
+        Initial::Gradient,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Initial::Noise => "Noise",
+            Initial::Center => "Center",
+            Initial::Symmetric => "Symmetric",
+            Initial::Sparse { .. } => "Sparse",
+            Initial::Gradient => "Gradient",
+            Initial::Image(_) => "Image",
+        }
+    }
+
+    /// Whether this is the same kind of generator as `other`, ignoring its settings.
+    pub fn same_kind(&self, other: &Initial) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    pub fn generate(&self, seed: u64) -> Blocks {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut blocks = Blocks::default();
+
+        match self {
+            Initial::Noise => return Blocks::from_rng(&mut rng),
+
+            Initial::Center => {
+                let color = rng.next_u32() | 0xff;
+                blocks[(GRID_SIZE / 2, GRID_SIZE / 2)] = Rgba::from(color);
+            }
+
+            Initial::Symmetric => {
+                let half = GRID_SIZE.div_ceil(2);
+                for r in 0..half {
+                    for c in 0..half {
+                        let color = Rgba::from(rng.next_u32());
+                        let (rr, cc) = (GRID_SIZE - 1 - r, GRID_SIZE - 1 - c);
+
+                        blocks[(r, c)] = color;
+                        blocks[(r, cc)] = color;
+                        blocks[(rr, c)] = color;
+                        blocks[(rr, cc)] = color;
+                    }
+                }
+            }
+
+            Initial::Sparse { density } => {
+                for r in 0..GRID_SIZE {
+                    for c in 0..GRID_SIZE {
+                        // Always draw both numbers so the pattern doesn't shift with the density.
+                        let roll: u8 = rng.gen_range(0..100);
+                        let color = rng.next_u32();
+                        if roll < *density {
+                            blocks[(r, c)] = Rgba::from(color);
+                        }
+                    }
+                }
+            }
+
+            Initial::Gradient => {
+                let from = (rng.next_u32() | 0xff).to_be_bytes();
+                let to = (rng.next_u32() | 0xff).to_be_bytes();
+                let steps = 2 * (GRID_SIZE as u32 - 1);
+
+                for r in 0..GRID_SIZE {
+                    for c in 0..GRID_SIZE {
+                        let t = (r + c) as u32;
+                        let mix = |i: usize| {
+                            let a = u32::from(from[i]) * (steps - t);
+                            let b = u32::from(to[i]) * t;
+                            ((a + b + steps / 2) / steps) as u8
+                        };
+                        blocks[(r, c)] =
+                            Rgba::from(u32::from_be_bytes([mix(0), mix(1), mix(2), mix(3)]));
+                    }
+                }
+            }
+
+            Initial::Image(image) => return **image,
+        }
+
+        blocks
+    }
+}
+
+impl std::fmt::Display for Initial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Initial::Noise => write!(f, "noise"),
+            Initial::Center => write!(f, "center"),
+            Initial::Symmetric => write!(f, "symmetric"),
+            Initial::Sparse { density } => write!(f, "sparse-{density}"),
+            Initial::Gradient => write!(f, "gradient"),
+            Initial::Image(image) => {
+                let compressed = miniz_oxide::deflate::compress_to_vec(&image.rgba8(), 9);
+                write!(f, "image-{}", BASE64_URL_SAFE_LENIENT.encode(compressed))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Initial {
+    type Err = ParseInitialError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "noise" => return Ok(Initial::Noise),
+            "center" => return Ok(Initial::Center),
+            "symmetric" => return Ok(Initial::Symmetric),
+            "gradient" => return Ok(Initial::Gradient),
+            _ => {}
+        }
+
+        if let Some(density) = s.strip_prefix("sparse-") {
+            return match density.parse() {
+                Ok(density) if density <= 100 => Ok(Initial::Sparse { density }),
+                _ => Err(ParseInitialError::Density(density.to_owned())),
+            };
+        }
+
+        if let Some(data) = s.strip_prefix("image-") {
+            let limit = GRID_SIZE * GRID_SIZE * 4;
+            let compressed = BASE64_URL_SAFE_LENIENT
+                .decode(data)
+                .map_err(|_| ParseInitialError::Image)?;
+            let rgba8 = miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, limit)
+                .map_err(|_| ParseInitialError::Image)?;
+            let image = Blocks::from_rgba8(&rgba8).ok_or(ParseInitialError::Image)?;
+
+            return Ok(Initial::Image(Box::new(image)));
+        }
+
+        Err(ParseInitialError::Unknown(s.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(blocks: &Blocks) -> usize {
+        blocks.rgba8().chunks(4).filter(|c| c != &[0; 4]).count()
+    }
+
+    #[test]
+    fn round_trips_through_strings() {
+        let mut image = Blocks::from_seed(1);
+        image[(0, 0)] = Rgba::from(0x1234_5678);
+
+        let cases = Initial::PATTERNS
+            .into_iter()
+            .chain([Initial::Image(Box::new(image))]);
+
+        for initial in cases {
+            let s = initial.to_string();
+            assert_eq!(s.parse::<Initial>().unwrap(), initial, "{s}");
+        }
+
+        assert!("sparse-101".parse::<Initial>().is_err());
+        assert!("image-AAAA".parse::<Initial>().is_err());
+        assert!("stripes".parse::<Initial>().is_err());
+    }
+
+    #[test]
+    fn noise_matches_from_seed() {
+        assert_eq!(Initial::Noise.generate(7), Blocks::from_seed(7));
+    }
+
+    #[test]
+    fn center_has_one_opaque_cell() {
+        let blocks = Initial::Center.generate(3);
+        let middle = u32::from(blocks[(GRID_SIZE / 2, GRID_SIZE / 2)]);
+
+        assert_eq!(filled(&blocks), 1);
+        assert_eq!(middle & 0xff, 0xff);
+    }
+
+    #[test]
+    fn symmetric_mirrors_both_ways() {
+        let blocks = Initial::Symmetric.generate(5);
+        let last = GRID_SIZE - 1;
+
+        for r in 0..GRID_SIZE {
+            for c in 0..GRID_SIZE {
+                assert_eq!(blocks[(r, c)], blocks[(last - r, c)]);
+                assert_eq!(blocks[(r, c)], blocks[(r, last - c)]);
+            }
+        }
+    }
+
+    #[test]
+    fn sparse_follows_density() {
+        assert_eq!(filled(&Initial::Sparse { density: 0 }.generate(1)), 0);
+        assert_eq!(
+            filled(&Initial::Sparse { density: 100 }.generate(1)),
+            GRID_SIZE * GRID_SIZE
+        );
+
+        let some = filled(&Initial::Sparse { density: 25 }.generate(1));
+        assert!((30..100).contains(&some), "{some}");
+
+        // Denser patterns keep every cell from sparser ones with the same seed.
+        let sparse = Initial::Sparse { density: 10 }.generate(9);
+        let dense = Initial::Sparse { density: 50 }.generate(9);
+        for r in 0..GRID_SIZE {
+            for c in 0..GRID_SIZE {
+                if u32::from(sparse[(r, c)]) != 0 {
+                    assert_eq!(sparse[(r, c)], dense[(r, c)]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gradient_runs_corner_to_corner() {
+        let blocks = Initial::Gradient.generate(2);
+        let mut rng = StdRng::seed_from_u64(2);
+        let from = rng.next_u32() | 0xff;
+        let to = rng.next_u32() | 0xff;
+
+        assert_eq!(u32::from(blocks[(0, 0)]), from);
+        assert_eq!(u32::from(blocks[(GRID_SIZE - 1, GRID_SIZE - 1)]), to);
+        assert_eq!(blocks[(0, 3)], blocks[(3, 0)]);
+    }
+}
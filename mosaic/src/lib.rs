@@ -0,0 +1,976 @@
+//! The platform-independent core of Mosaic: grids of colored blocks that evolve by running a Wasm
+//! module on each cell's neighborhood.
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+pub mod cases;
+pub mod check;
+pub mod dsl;
+pub mod export;
+pub mod host;
+pub mod initial;
+pub mod share;
+
+use host::HostState;
+pub use initial::Initial;
+pub use share::{DecodeModuleError, Share};
+
+const DEFAULT_UPDATE: &str = include_str!("data/default.wat");
+
+pub const GRID_SIZE: usize = 16;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Blocks([[Rgba; GRID_SIZE]; GRID_SIZE]);
+
+impl Default for Blocks {
+    fn default() -> Self {
+        Self([[Rgba::default(); GRID_SIZE]; GRID_SIZE])
+    }
+}
+
+impl Blocks {
+    pub fn from_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        Self::from_rng(&mut rng)
+    }
+
+    pub fn from_rng(rng: &mut dyn RngCore) -> Self {
+        let mut blocks = Self::default();
+
+        for r in 0..GRID_SIZE {
+            for c in 0..GRID_SIZE {
+                blocks.0[r][c] = Rgba::from(rng.next_u32());
+            }
+        }
+
+        blocks
+    }
+
+    /// A stable fingerprint of the block colors, for comparing runs without storing every frame.
+    pub fn digest(&self) -> u64 {
+        // 64-bit FNV-1a, since it's tiny and doesn't change between releases.
+        self.rgba8()
+            .iter()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+            })
+    }
+
+    /// Returns the pixel data in row-major order with one byte per channel (the layout
+    /// `ImageData` and most image encoders expect).
+    pub fn rgba8(&self) -> Vec<u8> {
+        self.0
+            .iter()
+            .flatten()
+            .flat_map(|rgba| u32::from(*rgba).to_be_bytes())
+            .collect()
+    }
+
+    /// Reads pixel data in the same layout [`Blocks::rgba8`] returns. The data must have exactly
+    /// one pixel per block.
+    pub fn from_rgba8(rgba8: &[u8]) -> Option<Self> {
+        if rgba8.len() != GRID_SIZE * GRID_SIZE * 4 {
+            return None;
+        }
+
+        let mut blocks = Self::default();
+        for (i, pixel) in rgba8.chunks_exact(4).enumerate() {
+            let pixel: [u8; 4] = pixel.try_into().expect("chunks of 4");
+            blocks.0[i / GRID_SIZE][i % GRID_SIZE] = Rgba::from(u32::from_be_bytes(pixel));
+        }
+
+        Some(blocks)
+    }
+
+    /// Fills a square brush of `size` cells on each side, centered on `(r, c)`. Even sizes put the
+    /// extra row and column below and to the right. The parts past the edges are dropped.
+    pub fn paint(&mut self, center: (usize, usize), size: usize, color: Rgba) {
+        fill_square(&mut self.0, center, size, color);
+    }
+}
+
+/// The hidden value of every cell, for modules that export `color` (see [`Interpreter`]). Each
+/// cell's state starts out as its initial color.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct States([[u32; GRID_SIZE]; GRID_SIZE]);
+
+impl States {
+    /// Paints states the same way [`Blocks::paint`] paints colors.
+    pub fn paint(&mut self, center: (usize, usize), size: usize, state: u32) {
+        fill_square(&mut self.0, center, size, state);
+    }
+}
+
+impl From<&Blocks> for States {
+    fn from(blocks: &Blocks) -> Self {
+        Self(blocks.0.map(|row| row.map(u32::from)))
+    }
+}
+
+impl std::ops::Index<(usize, usize)> for States {
+    type Output = u32;
+
+    fn index(&self, (r, c): (usize, usize)) -> &Self::Output {
+        &self.0[r][c]
+    }
+}
+
+impl std::ops::IndexMut<(usize, usize)> for States {
+    fn index_mut(&mut self, (r, c): (usize, usize)) -> &mut Self::Output {
+        &mut self.0[r][c]
+    }
+}
+
+/// Fills a square for [`Blocks::paint`] and [`States::paint`].
+fn fill_square<T: Copy>(
+    cells: &mut [[T; GRID_SIZE]; GRID_SIZE],
+    (r, c): (usize, usize),
+    size: usize,
+    value: T,
+) {
+    let start = |i: usize| i.saturating_sub(size.saturating_sub(1) / 2);
+    let end = |i: usize| (i + size / 2 + 1).min(GRID_SIZE);
+
+    for row in &mut cells[start(r)..end(r)] {
+        row[start(c)..end(c)].fill(value);
+    }
+}
+
+impl std::ops::Index<(usize, usize)> for Blocks {
+    type Output = Rgba;
+
+    fn index(&self, (r, c): (usize, usize)) -> &Self::Output {
+        &self.0[r][c]
+    }
+}
+
+impl std::ops::IndexMut<(usize, usize)> for Blocks {
+    fn index_mut(&mut self, (r, c): (usize, usize)) -> &mut Self::Output {
+        &mut self.0[r][c]
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rgba {
+    red: u8,
+    green: u8,
+    blue: u8,
+    alpha: u8,
+}
+
+impl Rgba {
+    pub const WHITE: Rgba = Rgba {
+        red: 255,
+        green: 255,
+        blue: 255,
+        alpha: 255,
+    };
+
+    /// Composites this color on top of `bottom` (the "over" operator with straight alpha).
+    pub fn over(self, bottom: Rgba) -> Rgba {
+        /// Multiplies two channels as if they were fractions of 255, rounding to nearest.
+        fn mul(a: u8, b: u8) -> u8 {
+            ((u32::from(a) * u32::from(b) + 127) / 255) as u8
+        }
+
+        let t = self.alpha;
+        let mix = |top: u8, bottom: u8| mul(bottom, 255 - t).saturating_add(mul(top, t));
+
+        Rgba {
+            red: mix(self.red, bottom.red),
+            green: mix(self.green, bottom.green),
+            blue: mix(self.blue, bottom.blue),
+            alpha: t.saturating_add(mul(bottom.alpha, 255 - t)),
+        }
+    }
+
+    pub fn css(&self) -> String {
+        let alpha_percent = f64::from(self.alpha) / 256f64;
+        format!(
+            "rgb({} {} {} / {})",
+            self.red, self.green, self.blue, alpha_percent,
+        )
+    }
+}
+
+impl From<u32> for Rgba {
+    fn from(v: u32) -> Self {
+        let [red, green, blue, alpha] = v.to_be_bytes();
+
+        Self {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+}
+
+impl From<Rgba> for u32 {
+    fn from(v: Rgba) -> Self {
+        u32::from_be_bytes([v.red, v.green, v.blue, v.alpha])
+    }
+}
+
+/// What the cells along the edges of the grid see past the edge.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EdgeMode {
+    /// The grid is a torus: the neighbors past one edge are the cells along the opposite edge.
+    #[default]
+    Wrap,
+
+    /// The neighbors past an edge are copies of the nearest cell on the edge.
+    Clamp,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("unknown edge mode {0:?} (expected \"wrap\" or \"clamp\")")]
+pub struct ParseEdgeModeError(String);
+
+impl EdgeMode {
+    pub const ALL: [EdgeMode; 2] = [EdgeMode::Wrap, EdgeMode::Clamp];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EdgeMode::Wrap => "Wrap",
+            EdgeMode::Clamp => "Clamp",
+        }
+    }
+
+    /// Returns the row or column `delta` cells away from `i`, handling the edges.
+    fn neighbor(self, i: usize, delta: isize) -> usize {
+        let j = i as isize + delta;
+        let n = GRID_SIZE as isize;
+
+        match self {
+            EdgeMode::Wrap => j.rem_euclid(n) as usize,
+            EdgeMode::Clamp => j.clamp(0, n - 1) as usize,
+        }
+    }
+}
+
+impl std::fmt::Display for EdgeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EdgeMode::Wrap => write!(f, "wrap"),
+            EdgeMode::Clamp => write!(f, "clamp"),
+        }
+    }
+}
+
+impl std::str::FromStr for EdgeMode {
+    type Err = ParseEdgeModeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wrap" => Ok(EdgeMode::Wrap),
+            "clamp" => Ok(EdgeMode::Clamp),
+            _ => Err(ParseEdgeModeError(s.to_owned())),
+        }
+    }
+}
+
+pub type Neighborhood = (u32, u32, u32, u32, u32, u32, u32, u32, u32);
+
+/// The number of params `next` takes when the module keeps state: the neighborhood's colors
+/// followed by their states, both in the same order.
+const STATEFUL_PARAMS: usize = 18;
+
+/// The amount of fuel each call to `next` (or `color`) gets before it's considered stuck.
+///
+/// This is generous enough for any reasonable rule while still stopping infinite loops before
+/// they freeze the page.
+pub const FUEL_PER_EVAL: u64 = 100_000;
+
+/// The most linear memory a module can have, in 64 KiB pages. Rules only need a little scratch
+/// space, and modules come from share links, so asking for gigabytes has to fail.
+pub const MAX_MEMORY_PAGES: usize = 16;
+
+/// The most elements a module's table can have, for the same reason as [`MAX_MEMORY_PAGES`].
+pub const MAX_TABLE_ELEMENTS: u32 = 1024;
+
+/// Where (and when) in the simulation a cell is being computed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Position {
+    pub tick: u64,
+    pub row: usize,
+    pub col: usize,
+}
+
+/// Runs a module's `next` function for every cell.
+///
+/// There are two ways to write a module. The original one exports `next` with nine `i32` params
+/// (the neighborhood's colors) that returns the cell's new color. A module that needs to remember
+/// more than a color (like age or direction) instead exports `next` with eighteen params (the
+/// colors, then the same cells' states) that returns the cell's new state, along with
+/// `color(state) -> i32` to decide what each state looks like.
+pub struct Interpreter {
+    store: wasmi::Store<HostState>,
+    abi: Abi,
+    edges: EdgeMode,
+
+    fuel_added: u64,
+}
+
+/// Which of the two kinds of modules described on [`Interpreter`] is running.
+enum Abi {
+    Colors {
+        next: wasmi::TypedFunc<Neighborhood, u32>,
+    },
+    States {
+        // Eighteen params is more than `TypedFunc` supports.
+        next: wasmi::Func,
+        color: wasmi::TypedFunc<u32, u32>,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InstantiateError {
+    #[error("invalid module: {0}")]
+    Compile(#[source] wasmi::Error),
+
+    #[error("could not bind host functions: {0}")]
+    Link(#[from] wasmi::errors::LinkerError),
+
+    #[error("could not instantiate module: {0}")]
+    Instantiate(#[source] wasmi::Error),
+
+    #[error("module does not export a `next` function")]
+    MissingNext,
+
+    #[error("`next` must take nine i32 params (or eighteen, to keep state) and return one i32, but its type is {found}")]
+    BadNext { found: String },
+
+    #[error("`next` takes states, so the module must also export a `color` function")]
+    MissingColor,
+
+    #[error("`color` must take one i32 param and return one i32, but its type is {found}")]
+    BadColor { found: String },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EvalError {
+    #[error("ran out of fuel (each cell gets {FUEL_PER_EVAL})")]
+    OutOfFuel,
+
+    #[error("trapped: {0}")]
+    Trap(#[source] wasmi::core::Trap),
+}
+
+impl EvalError {
+    fn from_trap(trap: wasmi::core::Trap) -> Self {
+        match trap.trap_code() {
+            Some(wasmi::core::TrapCode::OutOfFuel) => EvalError::OutOfFuel,
+            _ => EvalError::Trap(trap),
+        }
+    }
+}
+
+/// An error from evaluating a particular cell while computing a whole tick.
+#[derive(Debug, thiserror::Error)]
+#[error("tick {tick}, row {row}, column {col}: {source}")]
+pub struct StepError {
+    pub tick: u64,
+    pub row: usize,
+    pub col: usize,
+    #[source]
+    pub source: EvalError,
+}
+
+impl Interpreter {
+    pub fn new(update: &[u8], seed: u64) -> Result<Self, InstantiateError> {
+        let mut config = wasmi::Config::default();
+        config.consume_fuel(true);
+
+        let engine = wasmi::Engine::new(&config);
+        let module =
+            wasmi::Module::new(&engine, &mut &update[..]).map_err(InstantiateError::Compile)?;
+
+        let mut store = wasmi::Store::new(&engine, HostState::new(seed));
+        store.limiter(|state| &mut state.limits);
+
+        let mut linker = <wasmi::Linker<HostState>>::new(&engine);
+
+        host::bind(&mut store, &mut linker)?;
+
+        // The start function (if any) runs during instantiation, so it needs fuel too.
+        store
+            .add_fuel(FUEL_PER_EVAL)
+            .expect("fuel metering enabled");
+
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(InstantiateError::Instantiate)?;
+
+        let next = instance
+            .get_func(&store, "next")
+            .ok_or(InstantiateError::MissingNext)?;
+
+        let ty = next.ty(&store);
+        let stateful = ty.params().len() == STATEFUL_PARAMS
+            && ty
+                .params()
+                .iter()
+                .all(|p| *p == wasmi::core::ValueType::I32)
+            && ty.results() == [wasmi::core::ValueType::I32];
+
+        let abi = if stateful {
+            let color = instance
+                .get_func(&store, "color")
+                .ok_or(InstantiateError::MissingColor)?;
+            let color =
+                color
+                    .typed::<u32, u32>(&store)
+                    .map_err(|_| InstantiateError::BadColor {
+                        found: format!("{:?}", color.ty(&store)),
+                    })?;
+
+            Abi::States { next, color }
+        } else {
+            let next =
+                next.typed::<Neighborhood, u32>(&store)
+                    .map_err(|_| InstantiateError::BadNext {
+                        found: format!("{ty:?}"),
+                    })?;
+
+            Abi::Colors { next }
+        };
+
+        Ok(Interpreter {
+            store,
+            abi,
+            edges: EdgeMode::default(),
+            fuel_added: FUEL_PER_EVAL,
+        })
+    }
+
+    /// Changes what cells along the edges see as their neighbors. This defaults to wrapping.
+    pub fn with_edges(mut self, edges: EdgeMode) -> Self {
+        self.edges = edges;
+        self
+    }
+
+    /// Whether the module keeps state, as described on [`Interpreter`].
+    pub fn is_stateful(&self) -> bool {
+        matches!(self.abi, Abi::States { .. })
+    }
+
+    /// Evaluates one cell for a module that doesn't keep state, or one that does where every
+    /// neighbor's state is the same as its color. This returns the cell's new state, which is also
+    /// its color unless the module keeps state.
+    pub fn eval(&mut self, position: Position, neighbors: Neighborhood) -> Result<u32, EvalError> {
+        self.eval_with_states(position, neighbors, neighbors)
+    }
+
+    /// Evaluates one cell, returning its new state. Modules that don't keep state ignore `states`
+    /// and return a color.
+    pub fn eval_with_states(
+        &mut self,
+        position: Position,
+        neighbors: Neighborhood,
+        states: Neighborhood,
+    ) -> Result<u32, EvalError> {
+        self.refuel();
+        self.store.data_mut().move_to(position);
+
+        match &self.abi {
+            Abi::Colors { next } => next
+                .call(&mut self.store, neighbors)
+                .map_err(EvalError::from_trap),
+
+            Abi::States { next, .. } => {
+                let (a, b, c, d, e, f, g, h, i) = neighbors;
+                let (sa, sb, sc, sd, se, sf, sg, sh, si) = states;
+                let params = [
+                    a, b, c, d, e, f, g, h, i, sa, sb, sc, sd, se, sf, sg, sh, si,
+                ]
+                .map(|v| wasmi::Value::I32(v as i32));
+
+                let mut result = [wasmi::Value::I32(0)];
+                next.call(&mut self.store, &params, &mut result)
+                    .map_err(|err| match err {
+                        wasmi::Error::Trap(trap) => EvalError::from_trap(trap),
+                        err => EvalError::Trap(wasmi::core::Trap::new(err.to_string())),
+                    })?;
+
+                match result {
+                    [wasmi::Value::I32(v)] => Ok(v as u32),
+                    _ => unreachable!("`next` was checked to return an i32"),
+                }
+            }
+        }
+    }
+
+    /// Decides what a state looks like. States are already colors unless the module keeps state.
+    pub fn color(&mut self, state: u32) -> Result<u32, EvalError> {
+        let Abi::States { color, .. } = self.abi else {
+            return Ok(state);
+        };
+
+        self.refuel();
+        color
+            .call(&mut self.store, state)
+            .map_err(EvalError::from_trap)
+    }
+
+    /// Computes tick number `tick` from the blocks of the tick before it. This is only right for
+    /// modules that don't keep state, since it treats each block's color as its state.
+    pub fn step(&mut self, prev: &Blocks, tick: u64) -> Result<Blocks, StepError> {
+        let (next, _) = self.step_with_states(prev, &States::from(prev), tick)?;
+        Ok(next)
+    }
+
+    /// Computes tick number `tick` from the blocks and states of the tick before it.
+    pub fn step_with_states(
+        &mut self,
+        prev: &Blocks,
+        states: &States,
+        tick: u64,
+    ) -> Result<(Blocks, States), StepError> {
+        let mut next = Blocks::default();
+        let mut next_states = States::default();
+
+        let edges = self.edges;
+
+        for r in 0..GRID_SIZE {
+            for c in 0..GRID_SIZE {
+                let neighborhood = |at: &dyn Fn(isize, isize) -> u32| -> Neighborhood {
+                    (
+                        at(-1, -1),
+                        at(-1, 0),
+                        at(-1, 1),
+                        at(0, -1),
+                        at(0, 0),
+                        at(0, 1),
+                        at(1, -1),
+                        at(1, 0),
+                        at(1, 1),
+                    )
+                };
+                let cell = |dr: isize, dc: isize| (edges.neighbor(r, dr), edges.neighbor(c, dc));
+
+                let colors = neighborhood(&|dr, dc| prev[cell(dr, dc)].into());
+                let neighbor_states = neighborhood(&|dr, dc| states[cell(dr, dc)]);
+
+                let position = Position {
+                    tick,
+                    row: r,
+                    col: c,
+                };
+
+                let located = |source| StepError {
+                    tick,
+                    row: r,
+                    col: c,
+                    source,
+                };
+
+                let state = self
+                    .eval_with_states(position, colors, neighbor_states)
+                    .map_err(located)?;
+                let color = self.color(state).map_err(located)?;
+
+                next[(r, c)] = Rgba::from(color);
+                next_states[(r, c)] = state;
+            }
+        }
+
+        Ok((next, next_states))
+    }
+
+    /// Tops the store back up to exactly [`FUEL_PER_EVAL`] so every cell gets the same budget.
+    fn refuel(&mut self) {
+        let consumed = self.store.fuel_consumed().expect("fuel metering enabled");
+        let remaining = self.fuel_added - consumed;
+        let delta = FUEL_PER_EVAL - remaining;
+
+        self.store.add_fuel(delta).expect("fuel metering enabled");
+        self.fuel_added += delta;
+    }
+}
+
+/// Runs a module from its initial state, one tick at a time, without any UI.
+pub struct Runner {
+    interpreter: Interpreter,
+    seed: u64,
+
+    prev: Blocks,
+    next: Blocks,
+    states: States,
+    tick: u64,
+}
+
+impl Runner {
+    pub fn new(update: &[u8], seed: u64) -> Result<Self, InstantiateError> {
+        let next = Blocks::from_seed(seed);
+
+        Ok(Self {
+            interpreter: Interpreter::new(update, seed)?,
+            seed,
+            prev: Blocks::default(),
+            next,
+            states: States::from(&next),
+            tick: 0,
+        })
+    }
+
+    /// Changes what cells along the edges see as their neighbors. This defaults to wrapping.
+    pub fn with_edges(mut self, edges: EdgeMode) -> Self {
+        self.interpreter = self.interpreter.with_edges(edges);
+        self
+    }
+
+    /// Starts from a different initial state. This defaults to [`Initial::Noise`], and should be
+    /// set before stepping.
+    pub fn with_initial(mut self, initial: &Initial) -> Self {
+        self.next = initial.generate(self.seed);
+        self.states = States::from(&self.next);
+        self
+    }
+
+    /// Advances to the next tick. If this fails, the runner stays on the last good tick.
+    pub fn step(&mut self) -> Result<(), StepError> {
+        let tick = self.tick + 1;
+        let (next, states) = self
+            .interpreter
+            .step_with_states(&self.next, &self.states, tick)?;
+
+        self.prev = self.next;
+        self.next = next;
+        self.states = states;
+        self.tick = tick;
+        Ok(())
+    }
+
+    /// The number of the tick shown by [`Runner::next`]. The initial state is tick 0.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// The blocks from the tick before the current one.
+    pub fn prev(&self) -> &Blocks {
+        &self.prev
+    }
+
+    /// The blocks for the current tick.
+    pub fn next(&self) -> &Blocks {
+        &self.next
+    }
+
+    /// The states for the current tick. These are the same as the colors unless the module keeps
+    /// state.
+    pub fn states(&self) -> &States {
+        &self.states
+    }
+}
+
+/// The languages a module's source can be written in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Language {
+    /// The WebAssembly text format, which can do anything Wasm can.
+    #[default]
+    Wat,
+
+    /// The expression language in [`dsl`], which is shorter for simple rules.
+    Dsl,
+
+    /// A binary module compiled elsewhere (like from Rust or Zig). Its text is a read-only
+    /// disassembly.
+    Wasm,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("unknown language {0:?} (expected \"wat\", \"dsl\", or \"wasm\")")]
+pub struct ParseLanguageError(String);
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::Wat, Language::Dsl, Language::Wasm];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Language::Wat => "WAT",
+            Language::Dsl => "Expressions",
+            Language::Wasm => "Wasm binary",
+        }
+    }
+
+    /// The source to start from when writing a new module in this language.
+    pub fn example(&self) -> &'static str {
+        match self {
+            Language::Wat => DEFAULT_UPDATE,
+            Language::Dsl => dsl::EXAMPLE,
+            Language::Wasm => "",
+        }
+    }
+
+    /// Whether the module's text is its source, as opposed to a view of its binary.
+    pub fn is_text(&self) -> bool {
+        !matches!(self, Language::Wasm)
+    }
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Language::Wat => write!(f, "wat"),
+            Language::Dsl => write!(f, "dsl"),
+            Language::Wasm => write!(f, "wasm"),
+        }
+    }
+}
+
+impl std::str::FromStr for Language {
+    type Err = ParseLanguageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wat" => Ok(Language::Wat),
+            "dsl" => Ok(Language::Dsl),
+            "wasm" => Ok(Language::Wasm),
+            _ => Err(ParseLanguageError(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CompileError {
+    #[error("invalid WAT: {0}")]
+    Wat(#[from] wat::Error),
+
+    #[error("invalid rule: {0}")]
+    Dsl(#[from] dsl::Error),
+
+    #[error("binary modules can't be compiled from text")]
+    Binary,
+
+    #[error(transparent)]
+    Invalid(#[from] InstantiateError),
+
+    #[error("could not disassemble module: {0}")]
+    Disassemble(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    /// The language of `text`.
+    pub language: Language,
+    pub text: String,
+    pub binary: Vec<u8>,
+}
+
+impl std::fmt::Display for Module {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl Default for Module {
+    fn default() -> Self {
+        let text = String::from(DEFAULT_UPDATE);
+        let binary = wat::parse_str(&text).expect("default WAT is valid");
+        Self {
+            language: Language::Wat,
+            text,
+            binary,
+        }
+    }
+}
+
+impl Module {
+    /// Builds a module from WAT text.
+    pub fn new(text: String) -> Result<Self, wat::Error> {
+        let binary = wat::parse_str(&text)?;
+        Ok(Self {
+            language: Language::Wat,
+            text,
+            binary,
+        })
+    }
+
+    /// Builds a module from source text in any language.
+    pub fn compile(language: Language, text: String) -> Result<Self, CompileError> {
+        let binary = match language {
+            Language::Wat => wat::parse_str(&text)?,
+            Language::Dsl => wat::parse_str(dsl::compile(&text)?)?,
+            Language::Wasm => return Err(CompileError::Binary),
+        };
+
+        Ok(Self {
+            language,
+            text,
+            binary,
+        })
+    }
+
+    /// Accepts a binary module if it could run as a rule, and disassembles it for display.
+    pub fn from_binary(binary: Vec<u8>) -> Result<Self, CompileError> {
+        // Instantiating checks the `next` signature and the imports. A start function gets the
+        // same fuel limit as `next`, so it can't hang here.
+        Interpreter::new(&binary, 0)?;
+
+        let text = wasmprinter::print_bytes(&binary)
+            .map_err(|err| CompileError::Disassemble(err.to_string()))?;
+
+        Ok(Self {
+            language: Language::Wasm,
+            text,
+            binary,
+        })
+    }
+
+    /// The bytes that a share link stores: the text for source languages and the binary for
+    /// [`Language::Wasm`].
+    pub fn source_bytes(&self) -> &[u8] {
+        if self.language.is_text() {
+            self.text.as_bytes()
+        } else {
+            &self.binary
+        }
+    }
+
+    /// Reads the module from a share link's fragment, ignoring any other settings in it.
+    ///
+    /// This accepts both the current format and older links that only have base64 WAT text.
+    pub fn try_decode(hash: &str) -> Result<Self, DecodeModuleError> {
+        Share::try_decode(hash).map(|share| share.module)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modules_cannot_take_too_much_memory() {
+        let module = |memory: &str| {
+            let text = format!(
+                r#"(module
+                  {memory}
+                  (func (export "next")
+                    (param i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+                    (memory.grow (i32.const 1))))"#
+            );
+            wat::parse_str(text).unwrap()
+        };
+
+        let max = format!("(memory {MAX_MEMORY_PAGES})");
+        let mut interpreter = Interpreter::new(&module(&max), 0).unwrap();
+        let grown = interpreter.eval(Position::default(), Default::default());
+        assert_eq!(grown.unwrap(), u32::MAX, "growing past the limit fails");
+
+        let err = Interpreter::new(&module("(memory 65536)"), 0)
+            .err()
+            .unwrap();
+        assert!(matches!(err, InstantiateError::Instantiate(_)), "{err}");
+
+        let table = format!("(memory 1) (table {} funcref)", MAX_TABLE_ELEMENTS + 1);
+        let err = Interpreter::new(&module(&table), 0).err().unwrap();
+        assert!(matches!(err, InstantiateError::Instantiate(_)), "{err}");
+    }
+
+    #[test]
+    fn paint_centers_brush_and_clips_at_edges() {
+        let red = Rgba::from(0xff00_00ff);
+        let painted = |blocks: &Blocks| -> Vec<(usize, usize)> {
+            (0..GRID_SIZE)
+                .flat_map(|r| (0..GRID_SIZE).map(move |c| (r, c)))
+                .filter(|rc| blocks[*rc] == red)
+                .collect()
+        };
+
+        let mut blocks = Blocks::default();
+        blocks.paint((5, 5), 1, red);
+        assert_eq!(painted(&blocks), [(5, 5)]);
+
+        let mut blocks = Blocks::default();
+        blocks.paint((5, 5), 3, red);
+        assert_eq!(painted(&blocks).len(), 9);
+        assert_eq!(blocks[(4, 4)], red);
+        assert_eq!(blocks[(6, 6)], red);
+
+        let mut blocks = Blocks::default();
+        blocks.paint((5, 5), 2, red);
+        assert_eq!(painted(&blocks), [(5, 5), (5, 6), (6, 5), (6, 6)]);
+
+        let mut blocks = Blocks::default();
+        blocks.paint((0, GRID_SIZE - 1), 3, red);
+        assert_eq!(
+            painted(&blocks),
+            [
+                (0, GRID_SIZE - 2),
+                (0, GRID_SIZE - 1),
+                (1, GRID_SIZE - 2),
+                (1, GRID_SIZE - 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn stateful_modules_keep_hidden_state() {
+        // Counts how many ticks each cell has been alive in its state, and shows whether that's even
+        // or odd. The colors alone couldn't keep count.
+        let text = r#"(module
+          (func (export "next")
+            (param i32 i32 i32 i32 i32 i32 i32 i32 i32)
+            (param i32 i32 i32 i32 i32 i32 i32 i32 i32)
+            (result i32)
+            (i32.add (local.get 13) (i32.const 1)))
+          (func (export "color") (param i32) (result i32)
+            (select (i32.const -1) (i32.const 0xff)
+              (i32.and (local.get 0) (i32.const 1)))))"#;
+
+        let module = Module::new(String::from(text)).unwrap();
+        let mut runner = Runner::new(&module.binary, 3)
+            .unwrap()
+            .with_initial(&Initial::Sparse { density: 0 });
+
+        for tick in 1..=3 {
+            runner.step().unwrap();
+
+            let color = if tick % 2 == 1 { 0xffff_ffff } else { 0xff };
+            for r in 0..GRID_SIZE {
+                for c in 0..GRID_SIZE {
+                    assert_eq!(runner.states()[(r, c)], tick);
+                    assert_eq!(u32::from(runner.next()[(r, c)]), color);
+                }
+            }
+        }
+
+        let no_color = text.replace("(export \"color\")", "");
+        let module = Module::new(no_color).unwrap();
+        let err = Runner::new(&module.binary, 3).err().unwrap().to_string();
+        assert_eq!(
+            err,
+            "`next` takes states, so the module must also export a `color` function"
+        );
+    }
+
+    #[test]
+    fn binary_modules_are_checked_before_running() {
+        let binary = Module::default().binary;
+        let module = Module::from_binary(binary.clone()).unwrap();
+        assert_eq!(module.binary, binary);
+        assert!(module.text.contains("(export \"next\""), "{}", module.text);
+
+        let no_next = wat::parse_str("(module (func (export \"prev\")))").unwrap();
+        let err = Module::from_binary(no_next).unwrap_err().to_string();
+        assert_eq!(err, "module does not export a `next` function");
+
+        let bad_next = wat::parse_str("(module (func (export \"next\") (result i32) i32.const 0))");
+        let err = Module::from_binary(bad_next.unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("`next` must take nine i32 params"), "{err}");
+
+        let err = Module::from_binary(b"not wasm".to_vec())
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("invalid module"), "{err}");
+    }
+}
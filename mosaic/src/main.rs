@@ -0,0 +1,147 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, ValueEnum};
+use eyre::{Context, OptionExt};
+use url::Url;
+
+use mosaic::{export, EdgeMode, Initial, Language, Module, Runner, Share};
+
+/// Runs a Mosaic module without a browser.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// A `.wat`, `.rule`, or `.wasm` file, a Mosaic share URL, or just the `#...` fragment from
+    /// one.
+    module: String,
+
+    /// The seed for the initial state. Defaults to the share URL's seed (or 0).
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// What cells along the edges see past them: `wrap` or `clamp`. Defaults to the share URL's
+    /// setting (or `wrap`).
+    #[arg(long)]
+    edges: Option<EdgeMode>,
+
+    /// How to fill the grid before the first tick: `noise`, `center`, `symmetric`, `sparse-<percent>`,
+    /// or `gradient`. Defaults to the share URL's setting (or `noise`).
+    #[arg(long)]
+    initial: Option<Initial>,
+
+    /// How many ticks to run after the initial state.
+    #[arg(long, default_value_t = 10)]
+    ticks: u64,
+
+    /// What to output for each tick.
+    #[arg(long, value_enum, default_value_t = Format::Hash)]
+    format: Format,
+
+    /// Where to write image frames.
+    #[arg(long, default_value = ".")]
+    out: PathBuf,
+
+    /// Image pixels per block (in each direction).
+    #[arg(long, default_value_t = 1)]
+    scale: u32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Print one line per tick with a digest of the blocks.
+    Hash,
+
+    /// Write one PNG per tick with the blocks' exact colors (including alpha).
+    Png,
+
+    /// Write one PPM per tick, flattened the way the page shows it.
+    Ppm,
+}
+
+fn main() -> eyre::Result<()> {
+    let args = Args::parse();
+
+    let share = load_share(&args.module)?;
+    let seed = args.seed.or(share.seed).unwrap_or_default();
+    let edges = args.edges.unwrap_or(share.edges);
+    let initial = args.initial.unwrap_or(share.initial);
+
+    let mut runner = Runner::new(&share.module.binary, seed)?
+        .with_edges(edges)
+        .with_initial(&initial);
+
+    if args.format != Format::Hash {
+        fs::create_dir_all(&args.out).wrap_err("create output directory")?;
+    }
+
+    loop {
+        let tick = runner.tick();
+
+        match args.format {
+            Format::Hash => println!("{tick} {:016x}", runner.next().digest()),
+            Format::Png => {
+                let png = export::png(runner.next(), args.scale)?;
+                write_frame(&args.out, tick, "png", &png)?;
+            }
+            Format::Ppm => {
+                let flat = export::flatten(runner.prev(), runner.next());
+                let ppm = export::ppm(&flat, args.scale)?;
+                write_frame(&args.out, tick, "ppm", &ppm)?;
+            }
+        }
+
+        if tick == args.ticks {
+            break;
+        }
+        runner.step()?;
+    }
+
+    Ok(())
+}
+
+/// Reads the module from a file or share link, along with the link's settings if it had any.
+fn load_share(arg: &str) -> eyre::Result<Share> {
+    let path = Path::new(arg);
+    if path.is_file() {
+        let module = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("wasm") => Module::from_binary(fs::read(path).wrap_err("read module file")?)?,
+            extension => {
+                let language = match extension {
+                    Some("rule") => Language::Dsl,
+                    _ => Language::Wat,
+                };
+                let text = fs::read_to_string(path).wrap_err("read module file")?;
+                Module::compile(language, text)?
+            }
+        };
+
+        return Ok(Share {
+            module,
+            ..Default::default()
+        });
+    }
+
+    if let Ok(url) = Url::parse(arg) {
+        let fragment = url
+            .fragment()
+            .ok_or_eyre("share URL has no module fragment")?;
+
+        // Older links keep the seed in the query string.
+        let query_seed = url
+            .query_pairs()
+            .find(|(k, _)| k == "seed")
+            .map(|(_, v)| v.parse().wrap_err("invalid seed in share URL"))
+            .transpose()?;
+
+        let mut share = Share::try_decode(fragment)?;
+        share.seed = share.seed.or(query_seed);
+        return Ok(share);
+    }
+
+    Ok(Share::try_decode(arg)?)
+}
+
+fn write_frame(dir: &Path, tick: u64, extension: &str, bytes: &[u8]) -> eyre::Result<()> {
+    let path = dir.join(format!("tick-{tick:05}.{extension}"));
+    fs::write(&path, bytes).wrap_err_with(|| format!("write {}", path.display()))
+}
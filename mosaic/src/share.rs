@@ -0,0 +1,326 @@
+//! The URL fragment format for sharing a module along with the settings to run it.
+//!
+//! Current links look like `v2.<seed>.<size>.<edges>.<initial>.<language>.<data>.<tests>`,
+//! where `data` is the module's source text (or binary, for `wasm`) and `tests` is the text of
+//! its [test cases](crate::cases), both deflated and then base64-encoded (URL-safe, unpadded).
+//! The seed and tests may be empty. The first links were just the base64 WAT text with no
+//! prefix. The URL-safe base64 alphabet has no `.`, so the formats can't be confused.
+
+use base64::Engine as _;
+
+use crate::initial::ParseInitialError;
+use crate::{
+    CompileError, EdgeMode, Initial, Language, Module, ParseEdgeModeError, ParseLanguageError,
+    GRID_SIZE,
+};
+
+pub(crate) const BASE64_URL_SAFE_LENIENT: base64::engine::GeneralPurpose =
+    base64::engine::GeneralPurpose::new(
+        &base64::alphabet::URL_SAFE,
+        base64::engine::GeneralPurposeConfig::new()
+            .with_encode_padding(false)
+            .with_decode_padding_mode(base64::engine::DecodePaddingMode::Indifferent),
+    );
+
+const VERSION: &str = "v2";
+
+/// The number of dot-separated fields in a current link, including the version.
+const FIELDS: usize = 8;
+
+/// The longest module source to inflate, so a tiny link can't expand to fill all of memory.
+pub const MAX_SOURCE_LEN: usize = 1 << 20;
+
+/// Everything a share link says about how to run a simulation.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Share {
+    pub module: Module,
+
+    /// The seed for the initial state. Older links keep this in the query string instead.
+    pub seed: Option<u64>,
+
+    pub edges: EdgeMode,
+
+    pub initial: Initial,
+
+    /// The module's test cases, in the format [`cases::parse`](crate::cases::parse) reads. These
+    /// aren't checked until they're run.
+    pub tests: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DecodeModuleError {
+    #[error("empty string")]
+    Empty,
+
+    #[error("unsupported link version {0:?}")]
+    Version(String),
+
+    #[error("expected {expected} dot-separated fields, got {actual}")]
+    Fields { expected: usize, actual: usize },
+
+    #[error("invalid seed: {0}")]
+    Seed(#[source] std::num::ParseIntError),
+
+    #[error("invalid grid size: {0}")]
+    BadSize(#[source] std::num::ParseIntError),
+
+    #[error("grid size {0} is not supported (only {GRID_SIZE} is)")]
+    Size(usize),
+
+    #[error(transparent)]
+    Edges(#[from] ParseEdgeModeError),
+
+    #[error(transparent)]
+    Initial(#[from] ParseInitialError),
+
+    #[error(transparent)]
+    Language(#[from] ParseLanguageError),
+
+    #[error("invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+
+    #[error("invalid compressed data: {0}")]
+    Inflate(#[from] miniz_oxide::inflate::DecompressError),
+
+    #[error("invalid UTF-8: {0}")]
+    Utf8(#[from] std::string::FromUtf8Error),
+
+    #[error(transparent)]
+    Compile(#[from] CompileError),
+}
+
+impl Share {
+    pub fn decode(hash: &str) -> Option<Self> {
+        match Self::try_decode(hash) {
+            Ok(v) => Some(v),
+            Err(DecodeModuleError::Empty) => None,
+            Err(err) => {
+                tracing::error!({ ?hash, ?err }, "invalid URL hash");
+                None
+            }
+        }
+    }
+
+    pub fn try_decode(hash: &str) -> Result<Self, DecodeModuleError> {
+        // Remove the leading hash character (#) for convenience.
+        let hash = hash.trim_start_matches('#');
+        if hash.is_empty() {
+            return Err(DecodeModuleError::Empty);
+        }
+
+        let Some((version, rest)) = hash.split_once('.') else {
+            return Self::decode_v1(hash);
+        };
+
+        if version != VERSION {
+            return Err(DecodeModuleError::Version(version.to_owned()));
+        }
+
+        let fields: Vec<&str> = rest.split('.').collect();
+        let &[seed, size, edges, initial, language, data, tests] = &fields[..] else {
+            return Err(DecodeModuleError::Fields {
+                expected: FIELDS,
+                actual: fields.len() + 1,
+            });
+        };
+
+        let initial: Initial = initial.parse()?;
+        let language: Language = language.parse()?;
+
+        let seed = match seed {
+            "" => None,
+            seed => Some(seed.parse().map_err(DecodeModuleError::Seed)?),
+        };
+
+        let size: usize = size.parse().map_err(DecodeModuleError::BadSize)?;
+        if size != GRID_SIZE {
+            return Err(DecodeModuleError::Size(size));
+        }
+
+        let edges = edges.parse()?;
+
+        let inflated = inflate(data)?;
+        let module = if language.is_text() {
+            Module::compile(language, String::from_utf8(inflated)?)?
+        } else {
+            Module::from_binary(inflated)?
+        };
+
+        let tests = match tests {
+            "" => String::new(),
+            tests => String::from_utf8(inflate(tests)?)?,
+        };
+
+        Ok(Self {
+            module,
+            seed,
+            edges,
+            initial,
+            tests,
+        })
+    }
+
+    /// Reads a link from before the format was versioned, which only had the module text.
+    fn decode_v1(hash: &str) -> Result<Self, DecodeModuleError> {
+        let decoded = BASE64_URL_SAFE_LENIENT.decode(hash)?;
+        let text = String::from_utf8(decoded)?;
+
+        Ok(Self {
+            module: Module::new(text).map_err(CompileError::from)?,
+            ..Default::default()
+        })
+    }
+
+    pub fn encode(&self) -> String {
+        let tests = match self.tests.trim() {
+            "" => String::new(),
+            _ => deflate(self.tests.as_bytes()),
+        };
+
+        format!(
+            "{VERSION}.{seed}.{GRID_SIZE}.{edges}.{initial}.{language}.{data}.{tests}",
+            seed = self.seed.map(|s| s.to_string()).unwrap_or_default(),
+            edges = self.edges,
+            initial = self.initial,
+            language = self.module.language,
+            data = deflate(self.module.source_bytes()),
+        )
+    }
+}
+
+fn deflate(bytes: &[u8]) -> String {
+    let compressed = miniz_oxide::deflate::compress_to_vec(bytes, 9);
+    BASE64_URL_SAFE_LENIENT.encode(compressed)
+}
+
+fn inflate(data: &str) -> Result<Vec<u8>, DecodeModuleError> {
+    let compressed = BASE64_URL_SAFE_LENIENT.decode(data)?;
+    let inflated = miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, MAX_SOURCE_LEN)?;
+    Ok(inflated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy(text: &str) -> String {
+        BASE64_URL_SAFE_LENIENT.encode(text)
+    }
+
+    #[test]
+    fn round_trip() {
+        let share = Share {
+            module: Module::default(),
+            seed: Some(42),
+            edges: EdgeMode::Clamp,
+            initial: Initial::Sparse { density: 20 },
+            tests: String::from(crate::cases::EXAMPLE),
+        };
+
+        let encoded = share.encode();
+        assert!(
+            encoded.starts_with("v2.42.16.clamp.sparse-20.wat."),
+            "{encoded}"
+        );
+        assert_eq!(Share::try_decode(&format!("#{encoded}")).unwrap(), share);
+
+        let share = Share::default();
+        let encoded = share.encode();
+        assert!(encoded.ends_with('.'), "no tests: {encoded}");
+        assert_eq!(Share::try_decode(&encoded).unwrap(), share);
+
+        let module = Module::compile(Language::Dsl, String::from(crate::dsl::EXAMPLE)).unwrap();
+        let share = Share {
+            module,
+            ..Default::default()
+        };
+        let encoded = share.encode();
+        assert!(encoded.starts_with("v2..16.wrap.noise.dsl."), "{encoded}");
+        assert_eq!(Share::try_decode(&encoded).unwrap(), share);
+
+        let module = Module::from_binary(Module::default().binary).unwrap();
+        let share = Share {
+            module,
+            ..Default::default()
+        };
+        let encoded = share.encode();
+        assert!(encoded.starts_with("v2..16.wrap.noise.wasm."), "{encoded}");
+        assert_eq!(Share::try_decode(&encoded).unwrap(), share);
+    }
+
+    #[test]
+    fn compresses() {
+        let module = Module::default();
+        let share = Share {
+            module: module.clone(),
+            ..Default::default()
+        };
+
+        assert!(share.encode().len() < legacy(&module.text).len());
+    }
+
+    #[test]
+    fn decodes_legacy_links() {
+        let module = Module::default();
+
+        let share = Share::try_decode(&format!("#{}", legacy(&module.text))).unwrap();
+        assert_eq!(share.module, module);
+        assert_eq!(share.seed, None);
+        assert_eq!(share.edges, EdgeMode::Wrap);
+
+        assert_eq!(Module::try_decode(&legacy(&module.text)).unwrap(), module);
+    }
+
+    #[test]
+    fn rejects_bad_links() {
+        let data = Share::default().encode();
+        let data = data.rsplit('.').nth(1).unwrap();
+
+        let cases = [
+            ("", "empty string"),
+            (
+                "v9.1.16.wrap.noise.wat.AAAA.",
+                "unsupported link version \"v9\"",
+            ),
+            (
+                "v2.1.16.wrap.noise.wat.AAAA",
+                "expected 8 dot-separated fields, got 7",
+            ),
+            ("v2.x.16.wrap.noise.wat.AAAA.", "invalid seed"),
+            (
+                "v2.1.32.wrap.noise.wat.AAAA.",
+                "grid size 32 is not supported",
+            ),
+            (
+                "v2.1.16.bounce.noise.wat.AAAA.",
+                "unknown edge mode \"bounce\"",
+            ),
+            (
+                "v2.1.16.wrap.stripes.wat.AAAA.",
+                "unknown initial state \"stripes\"",
+            ),
+            (
+                "v2.1.16.wrap.sparse-200.wat.AAAA.",
+                "invalid density \"200\"",
+            ),
+            ("v2.1.16.wrap.noise.lisp.AAAA.", "unknown language \"lisp\""),
+            ("v2.1.16.wrap.noise.wat.AAAA.", "invalid compressed data"),
+            (
+                "v2.1.16.wrap.noise.dsl.AwA.",
+                "invalid rule: line 1, column 1: missing `next = ...`",
+            ),
+            ("v2.1.16.wrap.noise.wasm.AwA.", "invalid module"),
+        ];
+
+        for (hash, message) in cases {
+            let err = Share::try_decode(hash).unwrap_err().to_string();
+            assert!(err.starts_with(message), "{hash}: {err}");
+        }
+
+        assert!(Share::try_decode(&format!("v2..16.wrap.noise.wat.{data}.")).is_ok());
+
+        let err = Share::try_decode(&format!("v2..16.wrap.noise.wat.{data}.AAAA"));
+        let err = err.unwrap_err().to_string();
+        assert!(err.starts_with("invalid compressed data"), "{err}");
+    }
+}
@@ -0,0 +1,244 @@
+//! Runs known modules from known seeds and compares the results to checked-in snapshots.
+//!
+//! To accept new output after an intentional change, run the tests with `UPDATE_GOLDEN=1` and
+//! review the diff in `tests/golden/`.
+
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use mosaic::{Blocks, EdgeMode, Initial, Module, Rgba, Runner, GRID_SIZE};
+
+/// Copies the lower-right neighbor, so everything moves up and to the left by one cell per tick.
+const SHIFT_UP_LEFT: &str = r#"(module
+  (func (export "next")
+    (param $p00 i32) (param $p01 i32) (param $p02 i32)
+    (param $p10 i32) (param $p11 i32) (param $p12 i32)
+    (param $p20 i32) (param $p21 i32) (param $p22 i32)
+    (result i32)
+    (local.get $p22)))"#;
+
+/// Copies the upper neighbor, so everything moves down by one cell per tick.
+const SHIFT_DOWN: &str = r#"(module
+  (func (export "next")
+    (param $p00 i32) (param $p01 i32) (param $p02 i32)
+    (param $p10 i32) (param $p11 i32) (param $p12 i32)
+    (param $p20 i32) (param $p21 i32) (param $p22 i32)
+    (result i32)
+    (local.get $p01)))"#;
+
+/// Renders blocks as one line per row of `rrggbbaa` hex values.
+fn render(blocks: &Blocks) -> String {
+    let mut out = String::new();
+
+    for r in 0..GRID_SIZE {
+        let row: Vec<String> = (0..GRID_SIZE)
+            .map(|c| format!("{:08x}", u32::from(blocks[(r, c)])))
+            .collect();
+        writeln!(out, "{}", row.join(" ")).unwrap();
+    }
+
+    out
+}
+
+fn runner(text: &str, seed: u64) -> Runner {
+    let module = Module::new(String::from(text)).unwrap();
+    Runner::new(&module.binary, seed).unwrap()
+}
+
+/// Renders every tick (including the initial state) into one snapshot.
+fn run(mut runner: Runner, ticks: u64) -> String {
+    let mut out = render(runner.next());
+    for _ in 0..ticks {
+        runner.step().unwrap();
+        write!(out, "\n# tick {}\n{}", runner.tick(), render(runner.next())).unwrap();
+    }
+
+    out
+}
+
+fn assert_golden(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.txt"));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("read {}: {err}", path.display()));
+
+    assert!(
+        expected == actual,
+        "{name} does not match {} (rerun with UPDATE_GOLDEN=1 to accept)",
+        path.display(),
+    );
+}
+
+fn single_cell(r: usize, c: usize) -> Blocks {
+    let mut blocks = Blocks::default();
+    blocks[(r, c)] = Rgba::from(0xFF00_00FF);
+    blocks
+}
+
+#[test]
+fn default_rule_seed_0() {
+    let text = Module::default().text;
+    assert_golden("default_seed_0", &run(runner(&text, 0), 4));
+}
+
+#[test]
+fn default_rule_seed_42() {
+    let text = Module::default().text;
+    assert_golden("default_seed_42", &run(runner(&text, 42), 4));
+}
+
+#[test]
+fn shift_wraps_around_corners() {
+    assert_golden("shift_up_left_seed_7", &run(runner(SHIFT_UP_LEFT, 7), 2));
+}
+
+#[test]
+fn initial_states_seed_3() {
+    let mut out = String::new();
+    for initial in Initial::PATTERNS {
+        write!(out, "# {initial}\n{}\n", render(&initial.generate(3))).unwrap();
+    }
+
+    assert_golden("initial_states_seed_3", &out);
+}
+
+#[test]
+fn runner_starts_from_initial_state() {
+    let runner = runner(SHIFT_UP_LEFT, 3).with_initial(&Initial::Center);
+    assert_eq!(*runner.next(), Initial::Center.generate(3));
+}
+
+#[test]
+fn shift_wraps_top_edge_to_bottom() {
+    let module = Module::new(String::from(SHIFT_DOWN)).unwrap();
+    let mut interpreter = mosaic::Interpreter::new(&module.binary, 0).unwrap();
+
+    let start = single_cell(GRID_SIZE - 1, 3);
+    let next = interpreter.step(&start, 1).unwrap();
+
+    assert_eq!(next, single_cell(0, 3));
+}
+
+#[test]
+fn shift_wraps_corner_diagonally() {
+    let module = Module::new(String::from(SHIFT_UP_LEFT)).unwrap();
+    let mut interpreter = mosaic::Interpreter::new(&module.binary, 0).unwrap();
+
+    let start = single_cell(0, 0);
+    let next = interpreter.step(&start, 1).unwrap();
+
+    assert_eq!(next, single_cell(GRID_SIZE - 1, GRID_SIZE - 1));
+}
+
+#[test]
+fn shift_clamps_at_edges() {
+    let module = Module::new(String::from(SHIFT_DOWN)).unwrap();
+    let mut interpreter = mosaic::Interpreter::new(&module.binary, 0)
+        .unwrap()
+        .with_edges(EdgeMode::Clamp);
+
+    // The bottom row falls off the edge instead of wrapping around.
+    let next = interpreter.step(&single_cell(GRID_SIZE - 1, 3), 1).unwrap();
+    assert_eq!(next, Blocks::default());
+
+    // The top row sees itself above, so it stays put while also moving down.
+    let next = interpreter.step(&single_cell(0, 3), 1).unwrap();
+    let mut expected = single_cell(0, 3);
+    expected[(1, 3)] = expected[(0, 3)];
+    assert_eq!(next, expected);
+}
+
+#[test]
+fn clamped_corners() {
+    let runner = runner(SHIFT_UP_LEFT, 7).with_edges(EdgeMode::Clamp);
+    assert_golden("shift_up_left_clamp_seed_7", &run(runner, 2));
+}
+
+#[test]
+fn shift_returns_home_after_one_lap() {
+    let mut runner = runner(SHIFT_UP_LEFT, 99);
+    let start = *runner.next();
+
+    for _ in 0..GRID_SIZE {
+        runner.step().unwrap();
+    }
+
+    assert_eq!(runner.tick(), GRID_SIZE as u64);
+    assert_eq!(*runner.next(), start);
+}
+
+#[test]
+fn runner_matches_manual_steps() {
+    let module = Module::default();
+    let mut runner = Runner::new(&module.binary, 5).unwrap();
+    let mut interpreter = mosaic::Interpreter::new(&module.binary, 5).unwrap();
+
+    let mut blocks = Blocks::from_seed(5);
+    for tick in 1..=3 {
+        runner.step().unwrap();
+        let next = interpreter.step(&blocks, tick).unwrap();
+
+        assert_eq!(*runner.prev(), blocks);
+        assert_eq!(*runner.next(), next);
+        blocks = next;
+    }
+}
+
+#[test]
+fn runner_keeps_last_good_tick_after_error() {
+    // Clears every cell on the first tick, then traps on the second because the center is zero.
+    let text = r#"(module
+      (func (export "next")
+        (param i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+        (if (i32.eqz (local.get 4)) (then unreachable))
+        (i32.const 0)))"#;
+
+    let module = Module::new(String::from(text)).unwrap();
+    let mut runner = Runner::new(&module.binary, 3).unwrap();
+
+    runner.step().unwrap();
+    let good = *runner.next();
+
+    let err = runner.step().unwrap_err();
+    assert_eq!((err.tick, err.row, err.col), (2, 0, 0));
+
+    assert_eq!(runner.tick(), 1);
+    assert_eq!(*runner.next(), good);
+}
+
+#[test]
+fn rgba_round_trips_through_u32() {
+    let samples = [
+        0x0000_0000,
+        0xFFFF_FFFF,
+        0x1234_5678,
+        0x8000_0001,
+        0xDEAD_BEEF,
+    ];
+
+    // Also check a spread of values across the whole range.
+    let spread = (0..=u32::MAX).step_by(0x0101_0101 / 3);
+
+    for v in samples.into_iter().chain(spread) {
+        assert_eq!(u32::from(Rgba::from(v)), v, "{v:#010x}");
+    }
+}
+
+#[test]
+fn rgba_channels_are_big_endian() {
+    let blocks = {
+        let mut blocks = Blocks::default();
+        blocks[(0, 0)] = Rgba::from(0x1122_3344);
+        blocks
+    };
+
+    assert_eq!(&blocks.rgba8()[..4], &[0x11, 0x22, 0x33, 0x44]);
+    assert_eq!(Rgba::from(0x1122_3344).css(), "rgb(17 34 51 / 0.265625)");
+}
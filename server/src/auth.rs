@@ -97,6 +97,12 @@ impl CsrfToken {
     pub async fn load_from(session: &Session) -> TowerSessionsResult<Option<Self>> {
         session.get(CSRF_TOKEN_KEY).await
     }
+
+    /// Checks that this token (from a request header) is the one for the session.
+    pub async fn verify(&self, session: &Session) -> TowerSessionsResult<bool> {
+        let expected = Self::load_from(session).await?;
+        Ok(expected.is_some_and(|expected| expected == *self))
+    }
 }
 
 impl axum_extra::headers::Header for CsrfToken {
@@ -252,11 +258,17 @@ async fn upsert_user(
 
     let user = users::ActiveModel {
         recurse_user_id: ActiveValue::Set(profile.id),
+        name: ActiveValue::Set(Some(profile.name.clone())),
         ..Default::default()
     };
 
+    // Keep the name up to date so it can be shown as the author of public things.
     let _ = Users::insert(user)
-        .on_conflict(OnConflict::new().do_nothing().to_owned())
+        .on_conflict(
+            OnConflict::column(users::Column::RecurseUserId)
+                .update_column(users::Column::Name)
+                .to_owned(),
+        )
         .exec_without_returning(db)
         .await?;
 
@@ -1,6 +1,7 @@
 use std::env;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::extract::{FromRef, Query, State};
 use axum::http::StatusCode;
@@ -28,6 +29,7 @@ use uuid::Uuid;
 
 use crate::auth::{AuthService, AuthenticateParams, CsrfToken, User};
 use crate::recurse::{Profile, RecurseClient};
+use crate::trellis::{SecretsCipher, WeatherService};
 
 const OAUTH_RETURN_KEY: &str = "oauth_return";
 
@@ -35,7 +37,10 @@ const OAUTH_STATE_COOKIE: &str = "ebd_oauth_state";
 
 mod auth;
 mod orm;
+mod programs;
 mod recurse;
+mod trellis;
+mod weather;
 
 const COMMIT_HASH: &str = include_str!(concat!(env!("OUT_DIR"), "/commit_hash"));
 const SOURCE_URL: &str = include_str!(concat!(env!("OUT_DIR"), "/source_url"));
@@ -52,6 +57,7 @@ struct AppState {
     globals: Arc<Globals>,
     db: DatabaseConnection,
     auth_svc: AuthService,
+    weather_svc: WeatherService,
     cookie_key: Key,
     http_client: reqwest::Client,
 }
@@ -112,6 +118,11 @@ async fn main() -> eyre::Result<()> {
         Key::from(&bytes)
     };
 
+    let weather_cache_ttl = match env::var("WEATHER_CACHE_TTL_SECS") {
+        Ok(secs) => Duration::from_secs(secs.parse().wrap_err("WEATHER_CACHE_TTL_SECS")?),
+        Err(_) => trellis::DEFAULT_CACHE_TTL,
+    };
+
     let globals = Arc::new(Globals {
         source_url: String::from(SOURCE_URL),
         commit_hash: String::from(COMMIT_HASH),
@@ -156,11 +167,36 @@ async fn main() -> eyre::Result<()> {
         ),
     };
 
+    let weather_svc = WeatherService {
+        db: db_conn.clone(),
+        http_client: http_client.clone(),
+        providers: Default::default(),
+        cipher: SecretsCipher::new(&cookie_key),
+        cache_ttl: weather_cache_ttl,
+        cache: Default::default(),
+    };
+
     let app = Router::new()
         .nest(
             "/api",
             Router::new()
                 .route("/hello", get(hello))
+                .route(
+                    "/mosaic/programs",
+                    get(programs::list).post(programs::create),
+                )
+                .route(
+                    "/mosaic/programs/:slug",
+                    get(programs::show)
+                        .patch(programs::update)
+                        .delete(programs::delete),
+                )
+                .route("/mosaic/gallery", get(programs::gallery))
+                .route(
+                    "/trellis/secrets",
+                    get(trellis::secrets_show).put(trellis::secrets_update),
+                )
+                .route("/trellis/weather", get(trellis::weather))
                 .fallback(not_found),
         )
         .route("/oauth/start", get(oauth_start))
@@ -174,6 +210,7 @@ async fn main() -> eyre::Result<()> {
             db: db_conn,
             globals,
             auth_svc,
+            weather_svc,
             cookie_key,
             http_client,
         });
@@ -3,4 +3,6 @@
 pub mod prelude;
 
 pub mod greetings;
+pub mod mosaic_programs;
+pub mod trellis_secrets;
 pub mod users;
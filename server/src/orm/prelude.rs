@@ -1,4 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
 pub use super::greetings::Entity as Greetings;
+pub use super::mosaic_programs::Entity as MosaicPrograms;
+pub use super::trellis_secrets::Entity as TrellisSecrets;
 pub use super::users::Entity as Users;
@@ -11,9 +11,28 @@ pub struct Model {
     pub recurse_user_id: i64,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub name: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::mosaic_programs::Entity")]
+    MosaicPrograms,
+    #[sea_orm(has_one = "super::trellis_secrets::Entity")]
+    TrellisSecrets,
+}
+
+impl Related<super::mosaic_programs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MosaicPrograms.def()
+    }
+}
+
+impl Related<super::trellis_secrets::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TrellisSecrets.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,332 @@
+//! Saved Mosaic programs, which give long share links a short URL. Public programs are also
+//! listed in the gallery.
+
+use std::collections::HashMap;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use eyre::{Context, OptionExt};
+use rand::distributions::{Alphanumeric, DistString};
+use sea_orm::prelude::*;
+use sea_orm::{ActiveValue, QueryOrder, QuerySelect, TryInsertResult};
+use sea_query::OnConflict;
+use serde::{Deserialize, Serialize};
+use tower_sessions::Session;
+
+use crate::auth::{CsrfToken, User};
+use crate::orm::prelude::*;
+use crate::orm::{mosaic_programs, users};
+use crate::AppResult;
+
+const SLUG_LEN: usize = 8;
+
+/// How many random slugs to try before giving up. With 62^8 possibilities, needing more than one
+/// try means something else is wrong.
+const SLUG_ATTEMPTS: usize = 3;
+
+const MAX_NAME_LEN: usize = 100;
+
+/// How many of the newest public programs to show in the gallery.
+// TODO: Paginate once there are enough programs for this to matter.
+const GALLERY_LIMIT: u64 = 60;
+
+/// Share links are mostly compressed module text, so this is plenty for any reasonable program.
+const MAX_SHARE_LEN: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Program {
+    pub slug: String,
+    pub name: String,
+
+    /// The share link fragment, which has the module and its settings.
+    pub share: String,
+
+    /// Whether the program is listed in the gallery. Anyone with the link can see it either way.
+    pub public: bool,
+
+    /// The author's name, if they've logged in since names started being recorded.
+    pub author: Option<String>,
+
+    /// The program this one was forked from, if it still exists.
+    pub parent: Option<ProgramRef>,
+
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgramRef {
+    pub slug: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewProgram {
+    pub name: String,
+    pub share: String,
+
+    #[serde(default)]
+    pub public: bool,
+
+    /// The slug of the program this was forked from.
+    #[serde(default)]
+    pub parent: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProgramUpdate {
+    pub public: bool,
+}
+
+/// Fills in the author and parent for each program.
+async fn expand(
+    db: &DatabaseConnection,
+    rows: Vec<(mosaic_programs::Model, Option<users::Model>)>,
+) -> Result<Vec<Program>, DbErr> {
+    let parent_ids: Vec<Uuid> = rows.iter().filter_map(|(p, _)| p.parent_id).collect();
+
+    let parents: HashMap<Uuid, ProgramRef> = if parent_ids.is_empty() {
+        HashMap::new()
+    } else {
+        MosaicPrograms::find()
+            .filter(mosaic_programs::Column::Id.is_in(parent_ids))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|p| {
+                let parent = ProgramRef {
+                    slug: p.slug,
+                    name: p.name,
+                };
+                (p.id, parent)
+            })
+            .collect()
+    };
+
+    let programs = rows
+        .into_iter()
+        .map(|(program, user)| Program {
+            parent: program.parent_id.and_then(|id| parents.get(&id).cloned()),
+            author: user.and_then(|user| user.name),
+            slug: program.slug,
+            name: program.name,
+            share: program.share,
+            public: program.public,
+            created_at: program.created_at,
+        })
+        .collect();
+
+    Ok(programs)
+}
+
+async fn find_by_slug(db: &DatabaseConnection, slug: &str) -> Result<Option<Program>, DbErr> {
+    let row = MosaicPrograms::find()
+        .filter(mosaic_programs::Column::Slug.eq(slug))
+        .find_also_related(Users)
+        .one(db)
+        .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    Ok(expand(db, vec![row]).await?.pop())
+}
+
+/// Lists the current user's programs, newest first.
+pub async fn list(
+    State(db): State<DatabaseConnection>,
+    user: User,
+) -> AppResult<impl IntoResponse> {
+    let rows = MosaicPrograms::find()
+        .filter(mosaic_programs::Column::UserId.eq(user.id))
+        .order_by_desc(mosaic_programs::Column::CreatedAt)
+        .find_also_related(Users)
+        .all(&db)
+        .await
+        .wrap_err("list programs")?;
+
+    let programs = expand(&db, rows).await.wrap_err("expand programs")?;
+    Ok(Json(programs))
+}
+
+/// Lists the newest public programs from everyone.
+pub async fn gallery(State(db): State<DatabaseConnection>) -> AppResult<impl IntoResponse> {
+    let rows = MosaicPrograms::find()
+        .filter(mosaic_programs::Column::Public.eq(true))
+        .order_by_desc(mosaic_programs::Column::CreatedAt)
+        .limit(GALLERY_LIMIT)
+        .find_also_related(Users)
+        .all(&db)
+        .await
+        .wrap_err("list public programs")?;
+
+    let programs = expand(&db, rows).await.wrap_err("expand programs")?;
+    Ok(Json(programs))
+}
+
+/// Looks up a program by its slug. Anyone with the link can see it.
+pub async fn show(
+    State(db): State<DatabaseConnection>,
+    Path(slug): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let program = find_by_slug(&db, &slug).await.wrap_err("find program")?;
+
+    match program {
+        Some(program) => Ok(Json(program).into_response()),
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+pub async fn create(
+    State(db): State<DatabaseConnection>,
+    session: Session,
+    user: User,
+    csrf: CsrfToken,
+    Json(new): Json<NewProgram>,
+) -> AppResult<impl IntoResponse> {
+    if !csrf.verify(&session).await? {
+        return Ok(StatusCode::UNAUTHORIZED.into_response());
+    }
+
+    let name = new.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        let message = format!("name must have between 1 and {MAX_NAME_LEN} characters");
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, message).into_response());
+    }
+
+    if new.share.len() > MAX_SHARE_LEN {
+        let message = format!("share link must be at most {MAX_SHARE_LEN} bytes");
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, message).into_response());
+    }
+
+    // Store the current format even if the client sent an older link. Decoding compiles and
+    // instantiates the module, which is too much CPU to do on the async workers.
+    let decoded = tokio::task::spawn_blocking(move || {
+        mosaic::Share::try_decode(&new.share).map(|share| share.encode())
+    })
+    .await
+    .wrap_err("decode share link")?;
+
+    let share = match decoded {
+        Ok(share) => share,
+        Err(err) => {
+            let message = format!("invalid share link: {err}");
+            return Ok((StatusCode::UNPROCESSABLE_ENTITY, message).into_response());
+        }
+    };
+
+    let parent_id = match &new.parent {
+        None => None,
+        Some(slug) => {
+            let parent = MosaicPrograms::find()
+                .filter(mosaic_programs::Column::Slug.eq(slug))
+                .one(&db)
+                .await
+                .wrap_err("find parent program")?;
+
+            match parent {
+                Some(parent) => Some(parent.id),
+                None => {
+                    let message = format!("no program to fork with slug {slug:?}");
+                    return Ok((StatusCode::UNPROCESSABLE_ENTITY, message).into_response());
+                }
+            }
+        }
+    };
+
+    for _ in 0..SLUG_ATTEMPTS {
+        let slug = Alphanumeric.sample_string(&mut rand::thread_rng(), SLUG_LEN);
+
+        let program = mosaic_programs::ActiveModel {
+            slug: ActiveValue::Set(slug),
+            user_id: ActiveValue::Set(user.id),
+            name: ActiveValue::Set(name.to_owned()),
+            share: ActiveValue::Set(share.clone()),
+            public: ActiveValue::Set(new.public),
+            parent_id: ActiveValue::Set(parent_id),
+            ..Default::default()
+        };
+
+        let res = MosaicPrograms::insert(program)
+            .on_conflict(
+                OnConflict::column(mosaic_programs::Column::Slug)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .do_nothing()
+            .exec_with_returning(&db)
+            .await
+            .wrap_err("insert program")?;
+
+        if let TryInsertResult::Inserted(program) = res {
+            let program = find_by_slug(&db, &program.slug)
+                .await
+                .wrap_err("find new program")?
+                .ok_or_eyre("new program disappeared")?;
+
+            return Ok((StatusCode::CREATED, Json(program)).into_response());
+        }
+    }
+
+    Err(eyre::eyre!("no unused slug after {SLUG_ATTEMPTS} attempts").into())
+}
+
+/// Changes whether one of the current user's programs is in the gallery.
+pub async fn update(
+    State(db): State<DatabaseConnection>,
+    session: Session,
+    user: User,
+    csrf: CsrfToken,
+    Path(slug): Path<String>,
+    Json(update): Json<ProgramUpdate>,
+) -> AppResult<impl IntoResponse> {
+    if !csrf.verify(&session).await? {
+        return Ok(StatusCode::UNAUTHORIZED.into_response());
+    }
+
+    let res = MosaicPrograms::update_many()
+        .col_expr(mosaic_programs::Column::Public, Expr::value(update.public))
+        .filter(mosaic_programs::Column::Slug.eq(&slug))
+        .filter(mosaic_programs::Column::UserId.eq(user.id))
+        .exec(&db)
+        .await
+        .wrap_err("update program")?;
+
+    if res.rows_affected == 0 {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+
+    let program = find_by_slug(&db, &slug)
+        .await
+        .wrap_err("find updated program")?;
+
+    Ok(Json(program).into_response())
+}
+
+/// Deletes one of the current user's programs.
+pub async fn delete(
+    State(db): State<DatabaseConnection>,
+    session: Session,
+    user: User,
+    csrf: CsrfToken,
+    Path(slug): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    if !csrf.verify(&session).await? {
+        return Ok(StatusCode::UNAUTHORIZED.into_response());
+    }
+
+    let res = MosaicPrograms::delete_many()
+        .filter(mosaic_programs::Column::Slug.eq(slug))
+        .filter(mosaic_programs::Column::UserId.eq(user.id))
+        .exec(&db)
+        .await
+        .wrap_err("delete program")?;
+
+    if res.rows_affected == 0 {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
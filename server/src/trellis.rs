@@ -0,0 +1,271 @@
+//! Trellis secrets kept on the server, and the weather proxy that uses them. Browsers never see a
+//! saved API key again, and everyone looking at the same place shares one cached report.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use axum_extra::extract::cookie::Key;
+use eyre::Context;
+use sea_orm::prelude::*;
+use sea_orm::ActiveValue;
+use sea_query::OnConflict;
+use serde::{Deserialize, Serialize};
+use tower_sessions::Session;
+
+use crate::auth::{CsrfToken, User};
+use crate::orm::prelude::*;
+use crate::orm::trellis_secrets;
+use crate::weather::{Providers, Report, WeatherError, WeatherQuery};
+use crate::AppResult;
+
+/// How long to reuse a weather report when nothing else is configured. OpenWeatherMap only
+/// updates about this often anyway.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// The most reports to keep at once. Every different query gets its own entry, so without a limit
+/// anyone could fill the cache with made-up ones faster than they go stale.
+const MAX_CACHE_ENTRIES: usize = 1024;
+
+/// Keys are short, so anything longer is a mistake.
+const MAX_KEY_LEN: usize = 128;
+
+#[derive(Clone)]
+pub struct WeatherService {
+    pub db: DatabaseConnection,
+    pub http_client: reqwest::Client,
+    pub providers: Arc<Providers>,
+    pub cipher: SecretsCipher,
+    pub cache_ttl: Duration,
+    pub cache: Arc<Mutex<HashMap<WeatherQuery, Cached>>>,
+}
+
+pub struct Cached {
+    fetched_at: Instant,
+    report: Report,
+}
+
+/// Encrypts saved secrets with AES-256-GCM.
+///
+/// This uses the same key material as private cookies, but each secret is bound to its user ID
+/// (as associated data), so a ciphertext can't be passed off as a cookie or moved to another user.
+#[derive(Clone)]
+pub struct SecretsCipher(Aes256Gcm);
+
+const SECRETS_AAD_PREFIX: &[u8] = b"trellis_secrets/";
+
+impl SecretsCipher {
+    pub fn new(key: &Key) -> Self {
+        let cipher = Aes256Gcm::new_from_slice(key.encryption()).expect("cookie keys are 256 bits");
+        Self(cipher)
+    }
+
+    fn aad(user_id: Uuid) -> Vec<u8> {
+        [SECRETS_AAD_PREFIX, user_id.as_bytes()].concat()
+    }
+
+    /// Returns the nonce followed by the ciphertext.
+    fn encrypt(&self, user_id: Uuid, secret: &str) -> eyre::Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: secret.as_bytes(),
+            aad: &Self::aad(user_id),
+        };
+
+        let ciphertext = self
+            .0
+            .encrypt(&nonce, payload)
+            .map_err(|_| eyre::eyre!("could not encrypt secret"))?;
+
+        Ok([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    fn decrypt(&self, user_id: Uuid, data: &[u8]) -> eyre::Result<String> {
+        const NONCE_LEN: usize = 12;
+        if data.len() < NONCE_LEN {
+            eyre::bail!("encrypted secret is too short");
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: &Self::aad(user_id),
+        };
+
+        let plaintext = self
+            .0
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| eyre::eyre!("could not decrypt secret"))?;
+
+        String::from_utf8(plaintext).wrap_err("secret is not UTF-8")
+    }
+}
+
+/// Which secrets the user has saved. The secrets themselves never leave the server.
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretsStatus {
+    pub open_weather: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecretsUpdate {
+    /// The new OpenWeatherMap API key, or nothing to forget the saved one.
+    pub open_weather: Option<String>,
+}
+
+impl WeatherService {
+    async fn open_weather_key(&self, user_id: Uuid) -> eyre::Result<Option<String>> {
+        let secrets = TrellisSecrets::find_by_id(user_id)
+            .one(&self.db)
+            .await
+            .wrap_err("find secrets")?;
+
+        let Some(encrypted) = secrets.and_then(|s| s.open_weather) else {
+            return Ok(None);
+        };
+
+        self.cipher.decrypt(user_id, &encrypted).map(Some)
+    }
+
+    fn cached(&self, query: &WeatherQuery) -> Option<Report> {
+        let cache = self.cache.lock().expect("cache lock");
+        let cached = cache.get(query)?;
+
+        (cached.fetched_at.elapsed() < self.cache_ttl).then(|| cached.report.clone())
+    }
+
+    fn store(&self, query: WeatherQuery, report: Report) {
+        let mut cache = self.cache.lock().expect("cache lock");
+
+        // Stale entries would be refetched anyway, so they can go first.
+        cache.retain(|_, cached| cached.fetched_at.elapsed() < self.cache_ttl);
+
+        if cache.len() >= MAX_CACHE_ENTRIES && !cache.contains_key(&query) {
+            let oldest = cache
+                .iter()
+                .min_by_key(|(_, cached)| cached.fetched_at)
+                .map(|(query, _)| query.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
+            }
+        }
+
+        let fetched_at = Instant::now();
+        cache.insert(query, Cached { fetched_at, report });
+    }
+}
+
+/// Says which secrets the current user has saved.
+pub async fn secrets_show(
+    State(svc): State<WeatherService>,
+    user: User,
+) -> AppResult<impl IntoResponse> {
+    let secrets = TrellisSecrets::find_by_id(user.id)
+        .one(&svc.db)
+        .await
+        .wrap_err("find secrets")?;
+
+    Ok(Json(SecretsStatus {
+        open_weather: secrets.is_some_and(|s| s.open_weather.is_some()),
+    }))
+}
+
+/// Saves (or forgets) the current user's secrets.
+pub async fn secrets_update(
+    State(svc): State<WeatherService>,
+    session: Session,
+    user: User,
+    csrf: CsrfToken,
+    Json(update): Json<SecretsUpdate>,
+) -> AppResult<impl IntoResponse> {
+    if !csrf.verify(&session).await? {
+        return Ok(StatusCode::UNAUTHORIZED.into_response());
+    }
+
+    let key = update.open_weather.as_deref().map(str::trim);
+    let open_weather = match key {
+        None | Some("") => None,
+        Some(key) if key.len() > MAX_KEY_LEN => {
+            let message = format!("API key must be at most {MAX_KEY_LEN} bytes");
+            return Ok((StatusCode::UNPROCESSABLE_ENTITY, message).into_response());
+        }
+        Some(key) => Some(svc.cipher.encrypt(user.id, key)?),
+    };
+    let saved = open_weather.is_some();
+
+    let secrets = trellis_secrets::ActiveModel {
+        user_id: ActiveValue::Set(user.id),
+        open_weather: ActiveValue::Set(open_weather),
+        ..Default::default()
+    };
+
+    TrellisSecrets::insert(secrets)
+        .on_conflict(
+            OnConflict::column(trellis_secrets::Column::UserId)
+                .update_column(trellis_secrets::Column::OpenWeather)
+                .to_owned(),
+        )
+        .exec_without_returning(&svc.db)
+        .await
+        .wrap_err("save secrets")?;
+
+    Ok(Json(SecretsStatus {
+        open_weather: saved,
+    })
+    .into_response())
+}
+
+/// Gets the weather from the tile's provider, using the current user's saved API key if it needs
+/// one. (OpenWeatherMap is the only one that does.)
+pub async fn weather(
+    State(svc): State<WeatherService>,
+    user: User,
+    Query(query): Query<WeatherQuery>,
+) -> AppResult<impl IntoResponse> {
+    let provider = svc.providers.get(query.provider);
+
+    if let Err(err) = query.validate(provider) {
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, err.to_string()).into_response());
+    }
+
+    // Check for a key even when the report is cached, so the cache isn't a way around needing
+    // one.
+    let api_key = if provider.needs_key() {
+        match svc.open_weather_key(user.id).await? {
+            Some(key) => Some(key),
+            None => {
+                let message = WeatherError::MissingKey(provider.name()).to_string();
+                return Ok((StatusCode::UNPROCESSABLE_ENTITY, message).into_response());
+            }
+        }
+    } else {
+        None
+    };
+
+    if let Some(report) = svc.cached(&query) {
+        return Ok(Json(report).into_response());
+    }
+
+    match provider
+        .report(&svc.http_client, &query, api_key.as_deref())
+        .await
+    {
+        Ok(report) => {
+            svc.store(query, report.clone());
+            Ok(Json(report).into_response())
+        }
+        Err(err @ (WeatherError::Invalid(_) | WeatherError::MissingKey(_))) => {
+            Ok((StatusCode::UNPROCESSABLE_ENTITY, err.to_string()).into_response())
+        }
+        Err(err) => {
+            tracing::warn!({ ?err, ?query }, "weather provider error");
+            Ok((StatusCode::BAD_GATEWAY, err.to_string()).into_response())
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! Weather providers for Trellis. Each one fetches from its own API and turns the result into the
+//! same [`Report`], so the weather tile doesn't need to know where it came from.
+
+use axum::async_trait;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+mod open_meteo;
+mod open_weather_map;
+
+pub use open_meteo::OpenMeteo;
+pub use open_weather_map::OpenWeatherMap;
+
+/// The most days of forecast anyone can ask for. OpenWeatherMap's free forecast only goes this
+/// far ahead.
+pub const MAX_FORECAST_DAYS: u8 = 5;
+
+/// The weather somewhere, the same shape whichever provider it came from.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    pub name: String,
+    pub conditions: Vec<Condition>,
+    pub temperature: f64,
+    pub forecast: Vec<Day>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Condition {
+    pub description: String,
+
+    /// An image URL.
+    pub icon: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Day {
+    /// In `YYYY-MM-DD` form, in the location's own time zone.
+    pub date: String,
+    pub low: f64,
+    pub high: f64,
+
+    /// The conditions around midday.
+    pub condition: Option<Condition>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    #[default]
+    OpenWeatherMap,
+    OpenMeteo,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    /// Kelvin
+    #[default]
+    Standard,
+    /// Celsius
+    Metric,
+    /// Fahrenheit
+    Imperial,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+pub struct WeatherQuery {
+    #[serde(default)]
+    pub provider: ProviderKind,
+
+    /// Where to get the weather for, in whatever form the provider takes.
+    pub location: String,
+
+    #[serde(default)]
+    pub units: Units,
+
+    /// A language code for the descriptions, for providers that have them in more than English.
+    #[serde(default)]
+    pub lang: Option<String>,
+
+    #[serde(default)]
+    pub forecast_days: u8,
+}
+
+/// Language codes are short, so anything longer is a mistake.
+const MAX_LANG_LEN: usize = 8;
+
+impl WeatherQuery {
+    pub fn validate(&self, provider: &dyn Provider) -> Result<(), WeatherError> {
+        provider.check_location(&self.location)?;
+
+        if let Some(lang) = &self.lang {
+            let valid = lang.len() <= MAX_LANG_LEN
+                && lang.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_');
+            if !valid {
+                return Err(WeatherError::Invalid(format!("invalid language {lang:?}")));
+            }
+        }
+
+        if self.forecast_days > MAX_FORECAST_DAYS {
+            return Err(WeatherError::Invalid(format!(
+                "forecast can be at most {MAX_FORECAST_DAYS} days"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WeatherError {
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("no {0} API key saved")]
+    MissingKey(&'static str),
+
+    #[error("{provider} responded with {status}")]
+    Upstream {
+        provider: &'static str,
+        status: reqwest::StatusCode,
+    },
+
+    #[error(transparent)]
+    Fetch(#[from] reqwest::Error),
+}
+
+#[async_trait]
+pub trait Provider: Send + Sync {
+    /// The provider's name, for attribution and error messages.
+    fn name(&self) -> &'static str;
+
+    /// Whether [`Provider::report`] needs the user's API key for this provider.
+    fn needs_key(&self) -> bool;
+
+    /// Makes sure the location is one this provider understands, before sending it anywhere.
+    fn check_location(&self, location: &str) -> Result<(), WeatherError>;
+
+    async fn report(
+        &self,
+        client: &reqwest::Client,
+        query: &WeatherQuery,
+        api_key: Option<&str>,
+    ) -> Result<Report, WeatherError>;
+}
+
+/// Every provider, each pointed at its API.
+#[derive(Default)]
+pub struct Providers {
+    pub open_weather_map: OpenWeatherMap,
+    pub open_meteo: OpenMeteo,
+}
+
+impl Providers {
+    /// Points every provider at the same server, which is only useful for testing.
+    #[cfg(test)]
+    pub fn at(base_url: &Url) -> Self {
+        Self {
+            open_weather_map: OpenWeatherMap {
+                base_url: base_url.clone(),
+            },
+            open_meteo: OpenMeteo {
+                base_url: base_url.clone(),
+            },
+        }
+    }
+
+    pub fn get(&self, kind: ProviderKind) -> &dyn Provider {
+        match kind {
+            ProviderKind::OpenWeatherMap => &self.open_weather_map,
+            ProviderKind::OpenMeteo => &self.open_meteo,
+        }
+    }
+}
+
+/// Sends the request and parses the JSON response, treating any error status as the provider's
+/// fault.
+async fn fetch<T: serde::de::DeserializeOwned>(
+    provider: &'static str,
+    request: reqwest::RequestBuilder,
+) -> Result<T, WeatherError> {
+    let res = request.send().await?;
+
+    let status = res.status();
+    if !status.is_success() {
+        return Err(WeatherError::Upstream { provider, status });
+    }
+
+    Ok(res.json().await?)
+}
+
+fn endpoint(base_url: &Url, path: &str) -> Url {
+    base_url.join(path).expect("static path")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::SocketAddr;
+    use std::path::PathBuf;
+
+    use axum::extract::Query;
+    use axum::http::StatusCode;
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::get;
+    use axum::Router;
+
+    use super::*;
+
+    const MOCK_KEY: &str = "test-key";
+
+    fn fixture(name: &str) -> Response {
+        let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "fixtures", "weather", name]
+            .iter()
+            .collect();
+        let body = std::fs::read_to_string(path).expect("fixture exists");
+        ([(http::header::CONTENT_TYPE, "application/json")], body).into_response()
+    }
+
+    /// Like OpenWeatherMap, turns away requests without the right key.
+    fn keyed(query: HashMap<String, String>, name: &str) -> Response {
+        match query.get("appid").map(String::as_str) {
+            Some(MOCK_KEY) => fixture(name),
+            _ => StatusCode::UNAUTHORIZED.into_response(),
+        }
+    }
+
+    /// A stand-in for the providers' APIs that serves saved responses from `fixtures/weather`.
+    async fn mock_server() -> Url {
+        let app = Router::new()
+            .route(
+                "/data/2.5/weather",
+                get(|Query(q)| async move { keyed(q, "owm_weather.json") }),
+            )
+            .route(
+                "/data/2.5/forecast",
+                get(|Query(q)| async move { keyed(q, "owm_forecast.json") }),
+            )
+            .route(
+                "/v1/forecast",
+                get(|| async { fixture("open_meteo_forecast.json") }),
+            );
+
+        let listener = tokio::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .await
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        Url::parse(&format!("http://{addr}/")).unwrap()
+    }
+
+    fn query(provider: ProviderKind, location: &str, forecast_days: u8) -> WeatherQuery {
+        WeatherQuery {
+            provider,
+            location: location.to_owned(),
+            units: Units::Metric,
+            lang: None,
+            forecast_days,
+        }
+    }
+
+    async fn report(query: &WeatherQuery, key: Option<&str>) -> Result<Report, WeatherError> {
+        let providers = Providers::at(&mock_server().await);
+        let provider = providers.get(query.provider);
+
+        query.validate(provider)?;
+        provider.report(&reqwest::Client::new(), query, key).await
+    }
+
+    #[tokio::test]
+    async fn open_weather_map_reports_current_weather_and_days() {
+        let query = query(ProviderKind::OpenWeatherMap, "5128581", 5);
+        let report = report(&query, Some(MOCK_KEY)).await.unwrap();
+
+        assert_eq!(report.name, "New York");
+        assert_eq!(report.temperature, 21.4);
+
+        let descriptions: Vec<_> = report.conditions.iter().map(|c| &c.description).collect();
+        assert_eq!(descriptions, ["light rain", "mist"]);
+        assert_eq!(
+            report.conditions[0].icon,
+            "https://openweathermap.org/img/w/10d.png"
+        );
+
+        let dates: Vec<_> = report.forecast.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(dates, ["2024-07-01", "2024-07-02"]);
+
+        let first = &report.forecast[0];
+        assert_eq!((first.low, first.high), (10.0, 15.0));
+        let midday = first.condition.as_ref().unwrap();
+        assert_eq!(midday.description, "clear sky");
+    }
+
+    #[tokio::test]
+    async fn open_weather_map_needs_a_working_key() {
+        let query = query(ProviderKind::OpenWeatherMap, "5128581", 0);
+
+        let err = report(&query, None).await.unwrap_err();
+        assert!(matches!(err, WeatherError::MissingKey(_)), "{err}");
+
+        let err = report(&query, Some("wrong")).await.unwrap_err();
+        assert!(
+            matches!(err, WeatherError::Upstream { status, .. } if status == StatusCode::UNAUTHORIZED),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn open_meteo_reports_current_weather_and_days() {
+        let query = query(ProviderKind::OpenMeteo, "40.71,-74.01", 2);
+        let report = report(&query, None).await.unwrap();
+
+        assert_eq!(report.name, "40.71, -74.01");
+        assert_eq!(report.temperature, 22.5);
+        assert_eq!(report.conditions.len(), 1);
+        assert_eq!(report.conditions[0].description, "overcast");
+        assert_eq!(
+            report.conditions[0].icon,
+            "https://openweathermap.org/img/w/04d.png"
+        );
+
+        // The fixture has three days, but only two were asked for.
+        assert_eq!(report.forecast.len(), 2);
+        let second = &report.forecast[1];
+        assert_eq!(second.date, "2024-07-02");
+        assert_eq!((second.low, second.high), (18.0, 27.5));
+        assert_eq!(second.condition.as_ref().unwrap().description, "rain");
+    }
+
+    #[tokio::test]
+    async fn checks_locations_before_fetching() {
+        let cases = [
+            (ProviderKind::OpenWeatherMap, ""),
+            (ProviderKind::OpenWeatherMap, "51&appid=x"),
+            (ProviderKind::OpenMeteo, "New York"),
+            (ProviderKind::OpenMeteo, "91,0"),
+            (ProviderKind::OpenMeteo, "0,181"),
+        ];
+
+        for (provider, location) in cases {
+            let err = report(&query(provider, location, 0), Some(MOCK_KEY))
+                .await
+                .unwrap_err();
+            assert!(matches!(err, WeatherError::Invalid(_)), "{location}: {err}");
+        }
+
+        let err = report(&query(ProviderKind::OpenMeteo, "0,0", 9), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WeatherError::Invalid(_)), "{err}");
+    }
+}
@@ -0,0 +1,175 @@
+use axum::async_trait;
+use serde::Deserialize;
+use url::Url;
+
+use super::open_weather_map::icon_url;
+use super::{endpoint, fetch, Condition, Day, Provider, Report, Units, WeatherError, WeatherQuery};
+
+const NAME: &str = "Open-Meteo";
+
+/// Open-Meteo, which finds places by coordinates and doesn't need a key. Its descriptions are
+/// only in English.
+pub struct OpenMeteo {
+    pub base_url: Url,
+}
+
+impl Default for OpenMeteo {
+    fn default() -> Self {
+        Self {
+            base_url: Url::parse("https://api.open-meteo.com/").expect("static URL"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Response {
+    current: Current,
+    daily: Option<Daily>,
+}
+
+#[derive(Deserialize)]
+struct Current {
+    temperature_2m: f64,
+    weather_code: u8,
+    is_day: u8,
+}
+
+/// Each field has one entry per day.
+#[derive(Deserialize)]
+struct Daily {
+    time: Vec<String>,
+    weather_code: Vec<u8>,
+    temperature_2m_min: Vec<f64>,
+    temperature_2m_max: Vec<f64>,
+}
+
+/// Reads a location written as `latitude,longitude`.
+fn coordinates(location: &str) -> Option<(f64, f64)> {
+    let (latitude, longitude) = location.split_once(',')?;
+    let latitude: f64 = latitude.trim().parse().ok()?;
+    let longitude: f64 = longitude.trim().parse().ok()?;
+
+    let valid = (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude);
+    valid.then_some((latitude, longitude))
+}
+
+/// Describes a WMO weather code, borrowing OpenWeatherMap's matching icon.
+fn condition(code: u8, day: bool) -> Option<Condition> {
+    let (description, icon) = match code {
+        0 => ("clear sky", "01"),
+        1 => ("mainly clear", "02"),
+        2 => ("partly cloudy", "03"),
+        3 => ("overcast", "04"),
+        45 | 48 => ("fog", "50"),
+        51 | 53 | 55 => ("drizzle", "09"),
+        56 | 57 => ("freezing drizzle", "09"),
+        61 | 63 | 65 => ("rain", "10"),
+        66 | 67 => ("freezing rain", "13"),
+        71 | 73 | 75 => ("snow", "13"),
+        77 => ("snow grains", "13"),
+        80..=82 => ("rain showers", "09"),
+        85 | 86 => ("snow showers", "13"),
+        95 => ("thunderstorm", "11"),
+        96 | 99 => ("thunderstorm with hail", "11"),
+        _ => return None,
+    };
+
+    let time = if day { 'd' } else { 'n' };
+
+    Some(Condition {
+        description: description.to_owned(),
+        icon: icon_url(&format!("{icon}{time}")),
+    })
+}
+
+#[async_trait]
+impl Provider for OpenMeteo {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn needs_key(&self) -> bool {
+        false
+    }
+
+    fn check_location(&self, location: &str) -> Result<(), WeatherError> {
+        match coordinates(location) {
+            Some(_) => Ok(()),
+            None => Err(WeatherError::Invalid(format!(
+                "invalid coordinates {location:?} (expected latitude,longitude)"
+            ))),
+        }
+    }
+
+    async fn report(
+        &self,
+        client: &reqwest::Client,
+        query: &WeatherQuery,
+        _api_key: Option<&str>,
+    ) -> Result<Report, WeatherError> {
+        let (latitude, longitude) = coordinates(&query.location).ok_or_else(|| {
+            WeatherError::Invalid(format!("invalid coordinates {:?}", query.location))
+        })?;
+
+        // There's no Kelvin option, so convert from Celsius.
+        let (unit, offset) = match query.units {
+            Units::Standard => ("celsius", 273.15),
+            Units::Metric => ("celsius", 0.0),
+            Units::Imperial => ("fahrenheit", 0.0),
+        };
+
+        let mut request = client.get(endpoint(&self.base_url, "v1/forecast")).query(&[
+            ("latitude", latitude.to_string()),
+            ("longitude", longitude.to_string()),
+            (
+                "current",
+                String::from("temperature_2m,weather_code,is_day"),
+            ),
+            ("temperature_unit", String::from(unit)),
+            ("timezone", String::from("auto")),
+        ]);
+
+        if query.forecast_days > 0 {
+            request = request.query(&[
+                (
+                    "daily",
+                    String::from("weather_code,temperature_2m_min,temperature_2m_max"),
+                ),
+                ("forecast_days", query.forecast_days.to_string()),
+            ]);
+        }
+
+        let res: Response = fetch(NAME, request).await?;
+
+        let forecast = match res.daily {
+            None => Vec::new(),
+            Some(daily) => daily
+                .time
+                .into_iter()
+                .zip(daily.weather_code)
+                .zip(
+                    daily
+                        .temperature_2m_min
+                        .into_iter()
+                        .zip(daily.temperature_2m_max),
+                )
+                .take(query.forecast_days.into())
+                .map(|((date, code), (low, high))| Day {
+                    date,
+                    low: low + offset,
+                    high: high + offset,
+                    condition: condition(code, true),
+                })
+                .collect(),
+        };
+
+        Ok(Report {
+            name: format!("{latitude:.2}, {longitude:.2}"),
+            conditions: condition(res.current.weather_code, res.current.is_day != 0)
+                .into_iter()
+                .collect(),
+            temperature: res.current.temperature_2m + offset,
+            forecast,
+        })
+    }
+}
@@ -0,0 +1,199 @@
+use axum::async_trait;
+use serde::Deserialize;
+use time::{OffsetDateTime, UtcOffset};
+use url::Url;
+
+use super::{endpoint, fetch, Condition, Day, Provider, Report, Units, WeatherError, WeatherQuery};
+
+const NAME: &str = "OpenWeatherMap";
+
+/// OpenWeatherMap, which finds places by city ID and needs an API key.
+pub struct OpenWeatherMap {
+    pub base_url: Url,
+}
+
+impl Default for OpenWeatherMap {
+    fn default() -> Self {
+        Self {
+            base_url: Url::parse("https://api.openweathermap.org/").expect("static URL"),
+        }
+    }
+}
+
+/// Turns an icon name from a response into a link to the picture.
+pub fn icon_url(icon: &str) -> String {
+    format!("https://openweathermap.org/img/w/{icon}.png")
+}
+
+#[derive(Deserialize)]
+struct OwmResponse {
+    weather: Vec<OwmWeather>,
+    main: OwmMain,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct OwmWeather {
+    description: String,
+    icon: String,
+}
+
+#[derive(Deserialize)]
+struct OwmMain {
+    temp: f64,
+}
+
+#[derive(Deserialize)]
+struct OwmForecast {
+    list: Vec<OwmForecastEntry>,
+    city: OwmCity,
+}
+
+#[derive(Deserialize)]
+struct OwmForecastEntry {
+    dt: i64,
+    weather: Vec<OwmWeather>,
+    main: OwmMain,
+}
+
+#[derive(Deserialize)]
+struct OwmCity {
+    /// Seconds east of UTC.
+    timezone: i32,
+}
+
+impl From<&OwmWeather> for Condition {
+    fn from(weather: &OwmWeather) -> Self {
+        Self {
+            description: weather.description.clone(),
+            icon: icon_url(&weather.icon),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for OpenWeatherMap {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn needs_key(&self) -> bool {
+        true
+    }
+
+    fn check_location(&self, location: &str) -> Result<(), WeatherError> {
+        if location.is_empty() || !location.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(WeatherError::Invalid(format!(
+                "invalid city ID {location:?}"
+            )));
+        }
+        Ok(())
+    }
+
+    async fn report(
+        &self,
+        client: &reqwest::Client,
+        query: &WeatherQuery,
+        api_key: Option<&str>,
+    ) -> Result<Report, WeatherError> {
+        let api_key = api_key.ok_or(WeatherError::MissingKey(NAME))?;
+
+        let units = match query.units {
+            Units::Standard => "standard",
+            Units::Metric => "metric",
+            Units::Imperial => "imperial",
+        };
+        let params = [
+            ("id", query.location.as_str()),
+            ("appid", api_key),
+            ("units", units),
+            ("lang", query.lang.as_deref().unwrap_or("en")),
+        ];
+
+        let current: OwmResponse = fetch(
+            NAME,
+            client
+                .get(endpoint(&self.base_url, "data/2.5/weather"))
+                .query(&params),
+        )
+        .await?;
+
+        let forecast = if query.forecast_days > 0 {
+            let forecast: OwmForecast = fetch(
+                NAME,
+                client
+                    .get(endpoint(&self.base_url, "data/2.5/forecast"))
+                    .query(&params),
+            )
+            .await?;
+
+            daily(&forecast, query.forecast_days)
+        } else {
+            Vec::new()
+        };
+
+        Ok(Report {
+            name: current.name,
+            conditions: current.weather.iter().map(Condition::from).collect(),
+            temperature: current.main.temp,
+            forecast,
+        })
+    }
+}
+
+/// Sums up the forecast, which comes in three-hour steps, into one entry per day (in the
+/// location's own time zone).
+fn daily(forecast: &OwmForecast, days: u8) -> Vec<Day> {
+    struct Summary<'a> {
+        date: time::Date,
+        low: f64,
+        high: f64,
+        /// The hours away from noon, with the conditions then.
+        midday: Option<(u8, &'a OwmWeather)>,
+    }
+
+    let offset = UtcOffset::from_whole_seconds(forecast.city.timezone).unwrap_or(UtcOffset::UTC);
+
+    let mut summaries: Vec<Summary> = Vec::new();
+
+    for entry in &forecast.list {
+        let Ok(time) = OffsetDateTime::from_unix_timestamp(entry.dt) else {
+            continue;
+        };
+        let time = time.to_offset(offset);
+        let temp = entry.main.temp;
+        let midday = entry.weather.first().map(|w| (time.hour().abs_diff(12), w));
+
+        match summaries.last_mut() {
+            Some(day) if day.date == time.date() => {
+                day.low = day.low.min(temp);
+                day.high = day.high.max(temp);
+                let closer = match (midday, day.midday) {
+                    (Some((hours, _)), Some((best, _))) => hours < best,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                };
+                if closer {
+                    day.midday = midday;
+                }
+            }
+            _ => summaries.push(Summary {
+                date: time.date(),
+                low: temp,
+                high: temp,
+                midday,
+            }),
+        }
+    }
+
+    summaries
+        .into_iter()
+        .take(days.into())
+        .map(|day| Day {
+            date: day.date.to_string(),
+            low: day.low,
+            high: day.high,
+            condition: day.midday.map(|(_, weather)| Condition::from(weather)),
+        })
+        .collect()
+}
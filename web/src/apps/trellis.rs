@@ -1,42 +1,257 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// A Trellis board, kept in local storage. API keys are saved on the server instead.
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Config {
     pub layout: Layout,
-    pub secrets: Secrets,
+
+    /// Keys saved in the browser before they moved to the server. They're kept until a logged-in
+    /// user opens Trellis, which uploads them and then forgets them here.
+    #[serde(
+        default,
+        rename = "secrets",
+        skip_serializing_if = "LegacySecrets::is_empty"
+    )]
+    pub legacy_secrets: LegacySecrets,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
-pub struct Layout {
-    pub tiles: Vec<Tile>,
+pub struct LegacySecrets {
+    pub open_weather: Option<String>,
+}
+
+impl LegacySecrets {
+    pub fn is_empty(&self) -> bool {
+        self.open_weather.is_none()
+    }
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
-pub struct Secrets {
-    pub open_weather: Option<String>,
+pub struct Layout {
+    pub tiles: Vec<Tile>,
 }
 
+/// The most columns or rows a tile can span. The board is never wider than this.
+pub const MAX_SPAN: u8 = 4;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Tile {
     pub id: Uuid,
     pub data: Data,
-    // TODO: height/width hints
-    // TODO: title
+
+    /// Shown above the tile, if there is one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// How many columns and rows of the board the tile takes up, from 1 to [`MAX_SPAN`]. On
+    /// narrower screens, tiles are no wider than the board.
+    #[serde(default = "Tile::default_span")]
+    pub col_span: u8,
+    #[serde(default = "Tile::default_span")]
+    pub row_span: u8,
+}
+
+impl Tile {
+    pub fn new(data: Data) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            data,
+            title: None,
+            col_span: Self::default_span(),
+            row_span: Self::default_span(),
+        }
+    }
+
+    fn default_span() -> u8 {
+        1
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Data {
-    Clock, // TODO: option to show a specific time zone
+    Clock(Clock),
     Weather(Weather),
     Note(Note),
     Counter(Counter),
 }
 
+impl Data {
+    /// A fresh tile of each kind, in the order they're offered when adding one.
+    pub const ALL: [Data; 4] = [
+        Data::Clock(Clock::DEFAULT),
+        Data::Weather(Weather::DEFAULT),
+        Data::Note(Note {
+            text: String::new(),
+        }),
+        Data::Counter(Counter { value: 0 }),
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Data::Clock(_) => "Clock",
+            Data::Weather(_) => "Weather",
+            Data::Note(_) => "Note",
+            Data::Counter(_) => "Counter",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Clock {
+    /// An IANA time zone name, like `America/New_York`. Without one, the clock shows local time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+
+    /// Shown above the time instead of the time zone name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    pub twelve_hour: bool,
+
+    pub show_date: bool,
+    pub show_iso: bool,
+    pub show_unix: bool,
+
+    /// More time zones to list as a world clock, one row each. When there are any, these replace
+    /// the single big clock (and the ISO and Unix rows).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub world: Vec<String>,
+}
+
+impl Clock {
+    pub const DEFAULT: Self = Self {
+        time_zone: None,
+        label: None,
+        twelve_hour: false,
+        show_date: true,
+        show_iso: true,
+        show_unix: true,
+        world: Vec::new(),
+    };
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Weather {
+    /// Where the weather comes from. Older boards were all OpenWeatherMap.
+    #[serde(default)]
+    pub provider: Provider,
+
+    /// The OpenWeatherMap city ID.
     pub location_id: Option<String>,
+
+    /// The Open-Meteo location, as `latitude,longitude`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coordinates: Option<String>,
+
+    #[serde(default)]
+    pub units: Units,
+
+    /// An OpenWeatherMap language code for the descriptions, like `fr` or `zh_cn`. Without one,
+    /// they're in English. Open-Meteo's are always in English.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+
+    /// How many days of forecast to show under the current weather, up to
+    /// [`Weather::MAX_FORECAST_DAYS`]. Zero hides the forecast.
+    #[serde(default)]
+    pub forecast_days: u8,
+}
+
+impl Weather {
+    /// OpenWeatherMap's free forecast only goes this far ahead, so the server won't go further
+    /// for any provider.
+    pub const MAX_FORECAST_DAYS: u8 = 5;
+
+    pub const DEFAULT: Self = Self {
+        provider: Provider::OpenWeatherMap,
+        location_id: None,
+        coordinates: None,
+        units: Units::Imperial,
+        lang: None,
+        forecast_days: 0,
+    };
+
+    /// The location in whatever form the provider takes, if there is one.
+    pub fn location(&self) -> Option<&str> {
+        let location = match self.provider {
+            Provider::OpenWeatherMap => &self.location_id,
+            Provider::OpenMeteo => &self.coordinates,
+        };
+        location.as_deref().filter(|l| !l.is_empty())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Provider {
+    #[default]
+    OpenWeatherMap,
+    OpenMeteo,
+}
+
+impl Provider {
+    pub const ALL: [Provider; 2] = [Provider::OpenWeatherMap, Provider::OpenMeteo];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Provider::OpenWeatherMap => "OpenWeatherMap",
+            Provider::OpenMeteo => "Open-Meteo",
+        }
+    }
+
+    /// The name the server uses for this provider in its `provider` parameter.
+    pub fn param(&self) -> &'static str {
+        match self {
+            Provider::OpenWeatherMap => "open_weather_map",
+            Provider::OpenMeteo => "open_meteo",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    #[default]
+    Imperial,
+    Metric,
+    Standard,
+}
+
+impl Units {
+    pub const ALL: [Units; 3] = [Units::Imperial, Units::Metric, Units::Standard];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Units::Imperial => "Fahrenheit",
+            Units::Metric => "Celsius",
+            Units::Standard => "Kelvin",
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Units::Imperial => "℉",
+            Units::Metric => "℃",
+            Units::Standard => "K",
+        }
+    }
+
+    /// The name the server uses for these units in its `units` parameter.
+    pub fn param(&self) -> &'static str {
+        match self {
+            Units::Imperial => "imperial",
+            Units::Metric => "metric",
+            Units::Standard => "standard",
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -58,25 +273,20 @@ If you think this is cool, have an idea to share, or want to watch development,
 impl Config {
     pub fn starter() -> Self {
         Self {
-            secrets: Secrets { open_weather: None },
             layout: Layout {
                 tiles: vec![
+                    Tile::new(Data::Clock(Clock::default())),
+                    Tile::new(Data::Weather(Weather::DEFAULT)),
                     Tile {
-                        id: Uuid::new_v4(),
-                        data: Data::Clock,
-                    },
-                    Tile {
-                        id: Uuid::new_v4(),
-                        data: Data::Weather(Weather { location_id: None }),
-                    },
-                    Tile {
-                        id: Uuid::new_v4(),
-                        data: Data::Note(Note {
+                        title: Some(String::from("Notes")),
+                        col_span: 2,
+                        ..Tile::new(Data::Note(Note {
                             text: String::from(STARTER_NOTE),
-                        }),
+                        }))
                     },
                 ],
             },
+            legacy_secrets: LegacySecrets::default(),
         }
     }
 }
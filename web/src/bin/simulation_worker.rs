@@ -0,0 +1,7 @@
+use gloo::worker::Registrable;
+
+use web::simulation_worker::SimulationWorker;
+
+fn main() {
+    SimulationWorker::registrar().register();
+}
@@ -15,9 +15,15 @@ component!(counter);
 component!(error);
 component!(footer);
 component!(grid);
+component!(grid_canvas);
 component!(header);
 component!(note);
+component!(program_save);
+component!(program_thumbnail);
 component!(simulation);
 component!(simulation_editor);
+component!(simulation_export);
+component!(simulation_preview);
 component!(trellis_config_context);
 component!(weather);
+component!(weather_key);
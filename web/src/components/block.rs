@@ -1,3 +1,4 @@
+use web_sys::Element;
 use yew::prelude::*;
 
 use crate::apps::mosaic::Rgba;
@@ -9,6 +10,10 @@ pub struct BlockProps {
 
     pub background: Rgba,
     pub foreground: Rgba,
+
+    /// Called when the block is clicked or dragged over. Blocks without this are display-only.
+    #[prop_or_default]
+    pub onpaint: Option<Callback<()>>,
 }
 
 #[function_component]
@@ -16,9 +21,30 @@ pub fn Block(props: &BlockProps) -> Html {
     let bg = style("background-color", &props.background.css());
     let fg = style("background-color", &props.foreground.css());
 
+    let onpointerdown = props.onpaint.clone().map(|onpaint| {
+        Callback::from(move |e: PointerEvent| {
+            e.prevent_default();
+
+            // Touch input captures the pointer in the first block, which would keep the other
+            // blocks from seeing the drag.
+            if let Some(target) = e.target_dyn_into::<Element>() {
+                let _ = target.release_pointer_capture(e.pointer_id());
+            }
+
+            onpaint.emit(())
+        })
+    });
+    let onpointerenter = props.onpaint.clone().map(|onpaint| {
+        Callback::from(move |e: PointerEvent| {
+            if e.buttons() & 1 != 0 {
+                onpaint.emit(())
+            }
+        })
+    });
+
     html! {
         <div class={classes!("h-full", "w-full", "aspect-square", props.class.clone())} style={bg}>
-            <div class="h-full w-full" style={fg} />
+            <div class="h-full w-full" style={fg} {onpointerdown} {onpointerenter} />
         </div>
     }
 }
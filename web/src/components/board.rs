@@ -1,7 +1,7 @@
 use yew::prelude::*;
 
 use crate::apps::trellis;
-use crate::apps::trellis::{Config, Data};
+use crate::apps::trellis::{Config, Data, Tile, MAX_SPAN};
 use crate::components::*;
 
 #[derive(Properties, PartialEq, Debug)]
@@ -16,16 +16,7 @@ pub struct BoardProps {
 pub fn Board(props: &BoardProps) -> Html {
     let config_ctx = use_context::<TrellisConfigContext>().unwrap();
 
-    let class = classes!(
-        "grid",
-        "grid-cols-1",
-        "md:grid-cols-2",
-        "lg:grid-cols-3",
-        "xl:grid-cols-4",
-        "gap-1",
-        "auto-rows-fr",
-        props.class.clone(),
-    );
+    let class = classes!(board_grid(), props.class.clone());
 
     html! {
         <div {class}>
@@ -43,11 +34,10 @@ fn render_tiles(config_ctx: TrellisConfigContext) -> Vec<Html> {
         let config_ctx = config_ctx.clone();
 
         let child = match &tile.data {
-            Data::Clock => html! { <Clock /> },
+            Data::Clock(clock) => html! { <Clock settings={clock.clone()} /> },
             Data::Weather(weather) => {
-                let location_id = weather.location_id.clone().unwrap_or_default();
-                let owm_api_key = config.secrets.open_weather.clone().unwrap_or_default();
-                html! { <Weather {location_id} {owm_api_key} /> }
+                let settings = weather.clone();
+                html! { <Weather {settings} /> }
             }
             Data::Note(note) => {
                 let initial = note.text.clone();
@@ -83,8 +73,62 @@ fn render_tiles(config_ctx: TrellisConfigContext) -> Vec<Html> {
                 html! { <Counter {value} {onchange} />}
             }
         };
-        children.push(child);
+        children.push(frame(tile, child));
     }
 
     children
 }
+
+/// Puts a tile's title above it and sizes it to the spans it asked for.
+fn frame(tile: &Tile, child: Html) -> Html {
+    let class = classes!("flex", "flex-col", "min-h-0", tile_spans(tile));
+
+    html! {
+        <section key={tile.id.to_string()} {class}>
+            if let Some(title) = &tile.title {
+                <h4 class="px-2 truncate">{title}</h4>
+            }
+            <div class="flex-grow min-h-0">{child}</div>
+        </section>
+    }
+}
+
+/// The grid tiles are laid out in. The board editor uses it too, so tiles there take up the same
+/// space they will on the board.
+pub fn board_grid() -> Classes {
+    classes!(
+        "grid",
+        "grid-cols-1",
+        "md:grid-cols-2",
+        "lg:grid-cols-3",
+        "xl:grid-cols-4",
+        "gap-1",
+        "auto-rows-fr",
+    )
+}
+
+/// Sizes a tile in the [`board_grid`] to the spans it asked for.
+pub fn tile_spans(tile: &Tile) -> Classes {
+    classes!(col_span(tile.col_span), row_span(tile.row_span))
+}
+
+/// The board has fewer columns on smaller screens, so a wide tile only spans as many as there
+/// are. (Spanning more would add columns to the grid.) Tailwind only generates classes it can
+/// find written out in full, so these can't be built with `format!`.
+fn col_span(span: u8) -> Classes {
+    match span.clamp(1, MAX_SPAN) {
+        1 => classes!(),
+        2 => classes!("md:col-span-2"),
+        3 => classes!("md:col-span-2", "lg:col-span-3"),
+        _ => classes!("md:col-span-2", "lg:col-span-3", "xl:col-span-4"),
+    }
+}
+
+fn row_span(span: u8) -> Classes {
+    match span.clamp(1, MAX_SPAN) {
+        1 => classes!(),
+        2 => classes!("row-span-2"),
+        3 => classes!("row-span-3"),
+        _ => classes!("row-span-4"),
+    }
+}
@@ -1,7 +1,10 @@
-use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use time_tz::TimeZone;
+use web_sys::{HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
 use yew::prelude::*;
 
-use crate::apps::trellis::{Config, Layout, Secrets};
+use crate::apps::trellis;
+use crate::apps::trellis::{Config, Data, Provider, Tile, Units, MAX_SPAN};
+use crate::components::{board_grid, tile_spans, WeatherKey};
 
 #[derive(Properties, PartialEq, Debug)]
 pub struct BoardEditorProps {
@@ -14,14 +17,40 @@ pub struct BoardEditorProps {
 }
 
 pub struct BoardEditor {
-    secrets_ref: NodeRef,
+    config: Config,
+    view: View,
+
+    /// The tile being dragged to a new spot, by index.
+    dragging: Option<usize>,
+
     layout_ref: NodeRef,
 
+    /// Set when switching to the JSON view, so the textareas get filled once they exist.
+    fill_json: bool,
+
     errors: Vec<String>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    Visual,
+    Json,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum BoardEditorMsg {
+    /// Adds a fresh tile of the kind at this index in [`Data::ALL`].
+    AddTile(usize),
+    UpdateTile(usize, Box<Tile>),
+    RemoveTile(usize),
+    Move { from: usize, to: usize },
+
+    DragStart(usize),
+    Drop(usize),
+    DragEnd,
+
+    SetView(View),
+
     Save,
     Cancel,
 }
@@ -31,10 +60,13 @@ impl Component for BoardEditor {
 
     type Properties = BoardEditorProps;
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
         Self {
-            secrets_ref: NodeRef::default(),
+            config: ctx.props().config.clone(),
+            view: View::Visual,
+            dragging: None,
             layout_ref: NodeRef::default(),
+            fill_json: false,
             errors: Vec::new(),
         }
     }
@@ -45,8 +77,21 @@ impl Component for BoardEditor {
         let onsubmit = ctx.link().callback(|_| BoardEditorMsg::Save);
         let oncancel = ctx.link().callback(|_| BoardEditorMsg::Cancel);
 
+        let (switch_label, switch_to) = match self.view {
+            View::Visual => ("Edit as JSON (advanced)", View::Json),
+            View::Json => ("Back to the visual editor", View::Visual),
+        };
+        let onswitch = ctx
+            .link()
+            .callback(move |_| BoardEditorMsg::SetView(switch_to));
+
+        let editor = match self.view {
+            View::Visual => self.visual(ctx),
+            View::Json => self.json(),
+        };
+
         html! {
-            <div class={classes!("flex", "flex-col", props.class.clone())}>
+            <div class={classes!("flex", "flex-col", "gap-2", props.class.clone())}>
                 <div class="max-w-prose info self-center">{docs()}</div>
 
                 if !self.errors.is_empty() {
@@ -55,62 +100,107 @@ impl Component for BoardEditor {
                     </div>
                 }
 
-                <form action="javascript:void(0);" class="flex flex-col">
-                    <label for="secrets">{"Secrets"}</label>
-                    <textarea name="secrets" ref={self.secrets_ref.clone()} />
+                <form action="javascript:void(0);" class="flex flex-col gap-2">
+                    <div class="flex flex-row justify-end">
+                        <button type="button" onclick={onswitch}>{switch_label}</button>
+                    </div>
 
-                    <label for="layout">{"Module"}</label>
-                    <textarea name="layout" ref={self.layout_ref.clone()} />
+                    {editor}
 
                     <div class="flex flex-row justify-around">
                         <button type="button" onclick={oncancel}>{"Cancel"}</button>
                         <button type="button" onclick={onsubmit}>{"Submit"}</button>
                     </div>
                 </form>
+
+                <section class="flex flex-col gap-1">
+                    <h4>{"Weather"}</h4>
+                    <p>{"OpenWeatherMap tiles need an API key, which is saved on the server (right away, separately from the board). Open-Meteo tiles don't need one."}</p>
+                    <WeatherKey />
+                </section>
             </div>
         }
     }
 
-    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
-        if first_render {
-            let props = ctx.props();
-
-            let secrets = self.secrets_ref.cast::<HtmlTextAreaElement>().unwrap();
+    fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
+        if std::mem::take(&mut self.fill_json) {
             let layout = self.layout_ref.cast::<HtmlTextAreaElement>().unwrap();
-
-            fill_textarea(&secrets, &props.config.secrets).expect("always valid JSON");
-            fill_textarea(&layout, &props.config.layout).expect("always valid JSON");
+            fill_textarea(&layout, &self.config.layout).expect("always valid JSON");
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         let props = ctx.props();
+        let tiles = &mut self.config.layout.tiles;
 
         match msg {
-            BoardEditorMsg::Save => {
-                let secrets = self.secrets_ref.cast::<HtmlInputElement>().unwrap();
-                let layout = self.layout_ref.cast::<HtmlTextAreaElement>().unwrap();
-
-                let mut errors = Vec::new();
-                let mut config = Config::default();
-
-                match serde_json::from_str(&secrets.value()) {
-                    Ok(secrets) => config.secrets = secrets,
-                    Err(err) => errors.push(err.to_string()),
+            BoardEditorMsg::AddTile(kind) => {
+                tiles.push(Tile::new(Data::ALL[kind].clone()));
+                true
+            }
+            BoardEditorMsg::UpdateTile(index, tile) => {
+                tiles[index] = *tile;
+                true
+            }
+            BoardEditorMsg::RemoveTile(index) => {
+                tiles.remove(index);
+                true
+            }
+            BoardEditorMsg::Move { from, to } => {
+                if from == to || to >= tiles.len() {
+                    return false;
                 }
+                let tile = tiles.remove(from);
+                tiles.insert(to, tile);
+                true
+            }
 
-                match serde_json::from_str(&layout.value()) {
-                    Ok(layout) => config.layout = layout,
-                    Err(err) => errors.push(err.to_string()),
+            BoardEditorMsg::DragStart(index) => {
+                self.dragging = Some(index);
+                true
+            }
+            BoardEditorMsg::Drop(to) => match self.dragging.take() {
+                Some(from) => {
+                    ctx.link().send_message(BoardEditorMsg::Move { from, to });
+                    true
                 }
+                None => false,
+            },
+            BoardEditorMsg::DragEnd => self.dragging.take().is_some(),
 
-                if errors.is_empty() {
-                    props.onsubmit.emit(Some(config));
-                    false
-                } else {
-                    self.errors = errors;
-                    true
+            BoardEditorMsg::SetView(view) if view == self.view => false,
+            BoardEditorMsg::SetView(View::Json) => {
+                self.view = View::Json;
+                self.fill_json = true;
+                self.errors.clear();
+                true
+            }
+            BoardEditorMsg::SetView(View::Visual) => {
+                match self.read_json() {
+                    Ok(config) => {
+                        self.config = config;
+                        self.view = View::Visual;
+                        self.errors.clear();
+                    }
+                    Err(errors) => self.errors = errors,
                 }
+                true
+            }
+
+            BoardEditorMsg::Save => {
+                let config = match self.view {
+                    View::Visual => self.config.clone(),
+                    View::Json => match self.read_json() {
+                        Ok(config) => config,
+                        Err(errors) => {
+                            self.errors = errors;
+                            return true;
+                        }
+                    },
+                };
+
+                props.onsubmit.emit(Some(config));
+                false
             }
             BoardEditorMsg::Cancel => {
                 props.onsubmit.emit(None);
@@ -120,9 +210,403 @@ impl Component for BoardEditor {
     }
 }
 
+impl BoardEditor {
+    fn visual(&self, ctx: &Context<Self>) -> Html {
+        html! { <>
+            <div class="flex flex-row flex-wrap items-center gap-1">
+                <span>{"Add a tile:"}</span>
+                { for Data::ALL.iter().enumerate().map(|(kind, data)| {
+                    let onclick = ctx.link().callback(move |_| BoardEditorMsg::AddTile(kind));
+                    html! { <button type="button" {onclick}>{data.label()}</button> }
+                }) }
+            </div>
+
+            if self.config.layout.tiles.is_empty() {
+                <p class="notice">{"The board is empty. Add a tile to get started!"}</p>
+            }
+
+            <div class={board_grid()}>
+                { for self.config.layout.tiles.iter().enumerate().map(|(index, tile)| self.tile(ctx, index, tile)) }
+            </div>
+
+            <datalist id="time-zones">
+                { for time_zone_names().into_iter().map(|name| html! { <option value={name} /> }) }
+            </datalist>
+        </> }
+    }
+
+    fn tile(&self, ctx: &Context<Self>, index: usize, tile: &Tile) -> Html {
+        let link = ctx.link();
+        let count = self.config.layout.tiles.len();
+
+        let ondragstart = link.callback(move |e: DragEvent| {
+            // Firefox won't start dragging without some data.
+            if let Some(transfer) = e.data_transfer() {
+                let _ = transfer.set_data("text/plain", "");
+                transfer.set_effect_allowed("move");
+            }
+            BoardEditorMsg::DragStart(index)
+        });
+        let dragging = self.dragging.is_some();
+        let ondragover = Callback::from(move |e: DragEvent| {
+            if dragging {
+                // Allows dropping here.
+                e.prevent_default();
+            }
+        });
+        let ondrop = link.callback(move |e: DragEvent| {
+            e.prevent_default();
+            BoardEditorMsg::Drop(index)
+        });
+        let ondragend = link.callback(|_| BoardEditorMsg::DragEnd);
+
+        let onearlier = link.callback(move |_| BoardEditorMsg::Move {
+            from: index,
+            to: index.saturating_sub(1),
+        });
+        let onlater = link.callback(move |_| BoardEditorMsg::Move {
+            from: index,
+            to: index + 1,
+        });
+        let onremove = link.callback(move |_| BoardEditorMsg::RemoveTile(index));
+
+        let ontitle = on_tile_change(ctx, index, tile, |tile, title| {
+            let title = title.trim();
+            tile.title = (!title.is_empty()).then(|| title.to_owned());
+        });
+        let oncols = on_tile_change(ctx, index, tile, |tile, span| {
+            tile.col_span = span.parse().unwrap_or(1);
+        });
+        let onrows = on_tile_change(ctx, index, tile, |tile, span| {
+            tile.row_span = span.parse().unwrap_or(1);
+        });
+
+        let class = classes!(
+            "notice",
+            "flex",
+            "flex-col",
+            "gap-1",
+            tile_spans(tile),
+            (self.dragging == Some(index)).then_some("opacity-50"),
+        );
+
+        html! {
+            <section key={tile.id.to_string()} {class} {ondragover} {ondrop}>
+                <div class="flex flex-row items-center gap-1">
+                    <h4 class="flex-grow cursor-move select-none" draggable="true" title="Drag to move" {ondragstart} {ondragend}>
+                        {"⠿ "}{tile.data.label()}
+                    </h4>
+                    <button type="button" title="Move earlier" disabled={index == 0} onclick={onearlier}>{"←"}</button>
+                    <button type="button" title="Move later" disabled={index + 1 == count} onclick={onlater}>{"→"}</button>
+                    <button type="button" onclick={onremove}>{"Delete"}</button>
+                </div>
+
+                <label class="flex flex-col">
+                    {"Title"}
+                    <input type="text" value={tile.title.clone().unwrap_or_default()} onchange={ontitle} />
+                </label>
+
+                <div class="flex flex-row gap-4">
+                    <label>
+                        {"Width "}
+                        <select onchange={oncols}>{span_options(tile.col_span)}</select>
+                    </label>
+                    <label>
+                        {"Height "}
+                        <select onchange={onrows}>{span_options(tile.row_span)}</select>
+                    </label>
+                </div>
+
+                {settings(ctx, index, tile)}
+            </section>
+        }
+    }
+
+    fn json(&self) -> Html {
+        html! { <>
+            <label for="layout">{"Layout"}</label>
+            <textarea name="layout" ref={self.layout_ref.clone()} />
+        </> }
+    }
+
+    fn read_json(&self) -> Result<Config, Vec<String>> {
+        let layout = self.layout_ref.cast::<HtmlTextAreaElement>().unwrap();
+
+        let mut errors = Vec::new();
+        let mut config = self.config.clone();
+
+        match serde_json::from_str(&layout.value()) {
+            Ok(layout) => config.layout = layout,
+            Err(err) => errors.push(err.to_string()),
+        }
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// The form fields for whatever kind of tile this is.
+fn settings(ctx: &Context<BoardEditor>, index: usize, tile: &Tile) -> Html {
+    match &tile.data {
+        Data::Clock(clock) => clock_settings(ctx, index, tile, clock),
+        Data::Weather(weather) => weather_settings(ctx, index, tile, weather),
+        Data::Note(note) => {
+            let onchange = on_tile_change(ctx, index, tile, |tile, text| {
+                if let Data::Note(note) = &mut tile.data {
+                    note.text = text;
+                }
+            });
+
+            html! {
+                <label class="flex flex-col flex-grow">
+                    {"Text"}
+                    <textarea class="flex-grow" rows="3" value={note.text.clone()} {onchange} />
+                </label>
+            }
+        }
+        Data::Counter(counter) => {
+            let onchange = on_tile_change(ctx, index, tile, |tile, value| {
+                if let Data::Counter(counter) = &mut tile.data {
+                    counter.value = value.parse().unwrap_or(counter.value);
+                }
+            });
+
+            html! {
+                <label>
+                    {"Value "}
+                    <input type="number" value={counter.value.to_string()} {onchange} />
+                </label>
+            }
+        }
+    }
+}
+
+fn weather_settings(
+    ctx: &Context<BoardEditor>,
+    index: usize,
+    tile: &Tile,
+    weather: &trellis::Weather,
+) -> Html {
+    fn edit(tile: &mut Tile) -> &mut trellis::Weather {
+        match &mut tile.data {
+            Data::Weather(weather) => weather,
+            _ => unreachable!("only weather tiles have weather settings"),
+        }
+    }
+
+    let onprovider = on_tile_change(ctx, index, tile, |tile, provider| {
+        let provider = Provider::ALL.into_iter().find(|p| p.param() == provider);
+        edit(tile).provider = provider.unwrap_or_default();
+    });
+    let onlocation = on_tile_change(ctx, index, tile, |tile, id| {
+        let id = id.trim();
+        edit(tile).location_id = (!id.is_empty()).then(|| id.to_owned());
+    });
+    let oncoordinates = on_tile_change(ctx, index, tile, |tile, coordinates| {
+        let coordinates: String = coordinates.split_whitespace().collect();
+        edit(tile).coordinates = (!coordinates.is_empty()).then_some(coordinates);
+    });
+    let onunits = on_tile_change(ctx, index, tile, |tile, units| {
+        let units = Units::ALL.into_iter().find(|u| u.param() == units);
+        edit(tile).units = units.unwrap_or_default();
+    });
+    let onlang = on_tile_change(ctx, index, tile, |tile, lang| {
+        let lang = lang.trim();
+        edit(tile).lang = (!lang.is_empty()).then(|| lang.to_owned());
+    });
+    let ondays = on_tile_change(ctx, index, tile, |tile, days| {
+        edit(tile).forecast_days = days.parse().unwrap_or(0);
+    });
+
+    html! { <>
+        <label>
+            {"Provider "}
+            <select onchange={onprovider}>
+                { for Provider::ALL.iter().map(|provider| html! {
+                    <option value={provider.param()} selected={*provider == weather.provider}>{provider.label()}</option>
+                }) }
+            </select>
+        </label>
+        { match weather.provider {
+            Provider::OpenWeatherMap => html! {
+                <label class="flex flex-col">
+                    {"OpenWeather city ID"}
+                    <input type="text" value={weather.location_id.clone().unwrap_or_default()} onchange={onlocation} />
+                </label>
+            },
+            Provider::OpenMeteo => html! {
+                <label class="flex flex-col">
+                    {"Coordinates (latitude,longitude)"}
+                    <input type="text" placeholder="40.71,-74.01" value={weather.coordinates.clone().unwrap_or_default()} onchange={oncoordinates} />
+                </label>
+            },
+        } }
+        <div class="flex flex-row flex-wrap gap-x-4">
+            <label>
+                {"Units "}
+                <select onchange={onunits}>
+                    { for Units::ALL.iter().map(|units| html! {
+                        <option value={units.param()} selected={*units == weather.units}>{units.label()}</option>
+                    }) }
+                </select>
+            </label>
+            <label>
+                {"Forecast "}
+                <select onchange={ondays}>
+                    { for (0..=trellis::Weather::MAX_FORECAST_DAYS).map(|days| html! {
+                        <option value={days.to_string()} selected={days == weather.forecast_days}>
+                            { match days {
+                                0 => String::from("None"),
+                                1 => String::from("1 day"),
+                                days => format!("{days} days"),
+                            } }
+                        </option>
+                    }) }
+                </select>
+            </label>
+        </div>
+        if weather.provider == Provider::OpenWeatherMap {
+            <label class="flex flex-col">
+                {"Language (like fr or zh_cn, blank for English)"}
+                <input type="text" value={weather.lang.clone().unwrap_or_default()} onchange={onlang} />
+            </label>
+        }
+    </> }
+}
+
+fn clock_settings(
+    ctx: &Context<BoardEditor>,
+    index: usize,
+    tile: &Tile,
+    clock: &trellis::Clock,
+) -> Html {
+    fn edit(tile: &mut Tile) -> &mut trellis::Clock {
+        match &mut tile.data {
+            Data::Clock(clock) => clock,
+            _ => unreachable!("only clocks have clock settings"),
+        }
+    }
+
+    let ontimezone = on_tile_change(ctx, index, tile, |tile, name| {
+        let name = name.trim();
+        edit(tile).time_zone = (!name.is_empty()).then(|| name.to_owned());
+    });
+    let onlabel = on_tile_change(ctx, index, tile, |tile, label| {
+        let label = label.trim();
+        edit(tile).label = (!label.is_empty()).then(|| label.to_owned());
+    });
+    let onworld = on_tile_change(ctx, index, tile, |tile, names| {
+        edit(tile).world = names
+            .lines()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect();
+    });
+    let ontwelve = on_tile_toggle(ctx, index, tile, |tile, on| edit(tile).twelve_hour = on);
+    let ondate = on_tile_toggle(ctx, index, tile, |tile, on| edit(tile).show_date = on);
+    let oniso = on_tile_toggle(ctx, index, tile, |tile, on| edit(tile).show_iso = on);
+    let onunix = on_tile_toggle(ctx, index, tile, |tile, on| edit(tile).show_unix = on);
+
+    let unknown: Vec<&String> = clock
+        .time_zone
+        .iter()
+        .chain(&clock.world)
+        .filter(|name| time_tz::timezones::get_by_name(name).is_none())
+        .collect();
+
+    html! { <>
+        <label class="flex flex-col">
+            {"Time zone (blank for local time)"}
+            <input type="text" list="time-zones" placeholder="America/New_York" value={clock.time_zone.clone().unwrap_or_default()} onchange={ontimezone} />
+        </label>
+        <label class="flex flex-col">
+            {"Label"}
+            <input type="text" value={clock.label.clone().unwrap_or_default()} onchange={onlabel} />
+        </label>
+        <div class="flex flex-row flex-wrap gap-x-4">
+            <label><input type="checkbox" checked={clock.twelve_hour} onchange={ontwelve} />{" 12-hour"}</label>
+            <label><input type="checkbox" checked={clock.show_date} onchange={ondate} />{" Date"}</label>
+            <label><input type="checkbox" checked={clock.show_iso} onchange={oniso} />{" ISO"}</label>
+            <label><input type="checkbox" checked={clock.show_unix} onchange={onunix} />{" Unix"}</label>
+        </div>
+        <label class="flex flex-col">
+            {"World clock time zones (one per line)"}
+            <textarea rows="3" value={clock.world.join("\n")} onchange={onworld} />
+        </label>
+        if !unknown.is_empty() {
+            <div class="alert">
+                {"Unknown time zones: "}
+                {unknown.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(", ")}
+            </div>
+        }
+    </> }
+}
+
+/// Every time zone the clock knows about, for suggesting them in the editor.
+fn time_zone_names() -> Vec<&'static str> {
+    let mut names: Vec<_> = time_tz::timezones::iter().map(|tz| tz.name()).collect();
+    names.sort_unstable();
+    names
+}
+
+fn span_options(current: u8) -> Html {
+    html! {
+        { for (1..=MAX_SPAN).map(|span| html! {
+            <option value={span.to_string()} selected={span == current}>{span}</option>
+        }) }
+    }
+}
+
+/// Applies a field's new value to a copy of the tile and saves it in the editor.
+fn on_tile_change(
+    ctx: &Context<BoardEditor>,
+    index: usize,
+    tile: &Tile,
+    apply: fn(&mut Tile, String),
+) -> Callback<Event> {
+    let tile = tile.clone();
+
+    ctx.link().callback(move |e: Event| {
+        let value = if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+            input.value()
+        } else if let Some(select) = e.target_dyn_into::<HtmlSelectElement>() {
+            select.value()
+        } else {
+            e.target_unchecked_into::<HtmlTextAreaElement>().value()
+        };
+
+        let mut tile = tile.clone();
+        apply(&mut tile, value);
+        BoardEditorMsg::UpdateTile(index, Box::new(tile))
+    })
+}
+
+/// Like [`on_tile_change`], but for checkboxes.
+fn on_tile_toggle(
+    ctx: &Context<BoardEditor>,
+    index: usize,
+    tile: &Tile,
+    apply: fn(&mut Tile, bool),
+) -> Callback<Event> {
+    let tile = tile.clone();
+
+    ctx.link().callback(move |e: Event| {
+        let input: HtmlInputElement = e.target_unchecked_into();
+
+        let mut tile = tile.clone();
+        apply(&mut tile, input.checked());
+        BoardEditorMsg::UpdateTile(index, Box::new(tile))
+    })
+}
+
 fn docs() -> Html {
     html! { <>
-    <p>{"This could definitely use a better editing UI, but this is still much better than opening the console to write local storage directly."}</p>
+    <p>{"Add tiles from the buttons below, then drag them by their names (or use the arrows) to rearrange them. Wider and taller tiles take up more of the board, but never more columns than the screen has room for."}</p>
+    <p>{"Nothing is saved until you submit. The JSON view shows the same board as plain text, for copying it somewhere else or making changes in bulk."}</p>
     </> }
 }
 
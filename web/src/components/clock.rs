@@ -1,7 +1,18 @@
 use gloo::timers::callback::Timeout;
+use time::format_description::FormatItem;
+use time::macros::format_description;
 use time::OffsetDateTime;
+use time_tz::OffsetDateTimeExt;
 use yew::prelude::*;
 
+use crate::apps::trellis;
+
+#[derive(Properties, PartialEq, Debug)]
+pub struct ClockProps {
+    #[prop_or_default]
+    pub settings: trellis::Clock,
+}
+
 pub struct Clock {
     time: OffsetDateTime,
     timeout: Timeout,
@@ -13,7 +24,7 @@ pub enum ClockMsg {
 
 impl Component for Clock {
     type Message = ClockMsg;
-    type Properties = ();
+    type Properties = ClockProps;
 
     fn create(ctx: &Context<Self>) -> Self {
         let time = OffsetDateTime::now_local().unwrap();
@@ -44,11 +55,23 @@ impl Component for Clock {
         true
     }
 
-    fn view(&self, _ctx: &Context<Self>) -> Html {
-        use time::macros::format_description;
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let settings = &ctx.props().settings;
+
+        if !settings.world.is_empty() {
+            return self.world(settings);
+        }
+
+        let time = match &settings.time_zone {
+            None => self.time,
+            Some(name) => match in_zone(self.time, name) {
+                Some(time) => time,
+                None => return unknown_zone(name),
+            },
+        };
+        let heading = settings.label.as_deref().or(settings.time_zone.as_deref());
 
         let date = format_description!("[weekday], [day padding:none] [month repr:long] [year]");
-        let time = format_description!("[hour repr:24]:[minute]:[second]");
         let iso = format_description!(
             "[year]-[month]-[day]T[hour repr:24]:[minute]:[second][offset_hour sign:mandatory]:[offset_minute]"
         );
@@ -56,13 +79,81 @@ impl Component for Clock {
 
         html! {
             <div class="flex flex-col items-center justify-around full">
-                <div class="text-3xl">{self.time.format(date).unwrap()}</div>
-                <div class="text-7xl">{self.time.format(time).unwrap()}</div>
-                <div class="flex items-center justify-around self-stretch text-gray-400">
-                    <div data-clock-target="iso">{self.time.format(iso).unwrap()}</div>
-                    <div data-clock-target="unix">{self.time.format(unix).unwrap()}</div>
-                </div>
+                if let Some(heading) = heading {
+                    <div class="text-xl">{heading}</div>
+                }
+                if settings.show_date {
+                    <div class="text-3xl">{time.format(date).unwrap()}</div>
+                }
+                <div class="text-7xl">{time.format(time_format(settings, true)).unwrap()}</div>
+                if settings.show_iso || settings.show_unix {
+                    <div class="flex items-center justify-around self-stretch text-gray-400">
+                        if settings.show_iso {
+                            <div data-clock-target="iso">{time.format(iso).unwrap()}</div>
+                        }
+                        if settings.show_unix {
+                            <div data-clock-target="unix">{time.format(unix).unwrap()}</div>
+                        }
+                    </div>
+                }
+            </div>
+        }
+    }
+}
+
+impl Clock {
+    fn world(&self, settings: &trellis::Clock) -> Html {
+        let date = format_description!("[weekday repr:short], [day padding:none] [month repr:short]");
+
+        let row = |name: &String| {
+            let city = name.rsplit('/').next().unwrap_or(name).replace('_', " ");
+
+            html! {
+                <tr>
+                    <td class="pr-2" title={name.clone()}>{city}</td>
+                    if let Some(time) = in_zone(self.time, name) {
+                        <td class="text-3xl text-right tabular-nums">{time.format(time_format(settings, false)).unwrap()}</td>
+                        if settings.show_date {
+                            <td class="pl-2 text-right text-gray-400">{time.format(date).unwrap()}</td>
+                        }
+                    } else {
+                        <td class="text-right" colspan="2">{"Unknown time zone"}</td>
+                    }
+                </tr>
+            }
+        };
+
+        html! {
+            <div class="flex flex-col items-center justify-around full">
+                if let Some(label) = &settings.label {
+                    <div class="text-xl">{label}</div>
+                }
+                <table>
+                    { for settings.world.iter().map(row) }
+                </table>
             </div>
         }
     }
 }
+
+/// The time right now in the IANA time zone with this name, if there is one.
+fn in_zone(time: OffsetDateTime, name: &str) -> Option<OffsetDateTime> {
+    time_tz::timezones::get_by_name(name).map(|tz| time.to_timezone(tz))
+}
+
+fn time_format(settings: &trellis::Clock, seconds: bool) -> &'static [FormatItem<'static>] {
+    match (settings.twelve_hour, seconds) {
+        (false, true) => format_description!("[hour repr:24]:[minute]:[second]"),
+        (false, false) => format_description!("[hour repr:24]:[minute]"),
+        (true, true) => format_description!("[hour repr:12 padding:none]:[minute]:[second] [period]"),
+        (true, false) => format_description!("[hour repr:12 padding:none]:[minute] [period]"),
+    }
+}
+
+fn unknown_zone(name: &str) -> Html {
+    html! {
+        <div class="flex flex-col items-center justify-around full">
+            <div class="alert">{format!("Unknown time zone {name:?}")}</div>
+        </div>
+    }
+}
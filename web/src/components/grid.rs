@@ -11,6 +11,10 @@ pub struct GridProps {
 
     pub prev: Blocks,
     pub next: Blocks,
+
+    /// Called with the row and column of each block that's clicked or dragged over.
+    #[prop_or_default]
+    pub onpaint: Option<Callback<(usize, usize)>>,
 }
 
 #[function_component]
@@ -23,15 +27,21 @@ pub fn Grid(props: &GridProps) -> Html {
 
             let background = props.prev[(r, c)];
             let foreground = props.next[(r, c)];
+            let onpaint = props.onpaint.as_ref().map(|onpaint| onpaint.reform(move |()| (r, c)));
 
             children.push(html! {
-                <Block {key} {background} {foreground} />
+                <Block {key} {background} {foreground} {onpaint} />
             });
         }
     }
 
+    let paintable = props
+        .onpaint
+        .is_some()
+        .then_some(classes!("touch-none", "cursor-crosshair"));
+
     html! {
-        <div class={classes!("grid", "grid-rows-[repeat(16,_1fr)]", "grid-cols-[repeat(16,_1fr)]", props.class.clone())}>
+        <div class={classes!("grid", "grid-rows-[repeat(16,_1fr)]", "grid-cols-[repeat(16,_1fr)]", paintable, props.class.clone())}>
             { children }
         </div>
     }
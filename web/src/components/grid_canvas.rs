@@ -0,0 +1,68 @@
+use web_sys::wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+use yew::prelude::*;
+
+use crate::apps::mosaic::{Blocks, GRID_SIZE};
+
+#[derive(Properties, PartialEq, Debug)]
+pub struct GridCanvasProps {
+    #[prop_or_default]
+    pub class: Classes,
+
+    pub prev: Blocks,
+    pub next: Blocks,
+}
+
+/// Draws the same picture as [`Grid`](super::Grid), but onto canvases instead of a div per block.
+///
+/// Each layer is its own canvas, one pixel per block, stacked so the browser blends `next` over
+/// `prev` the same way it blends the nested block divs.
+#[function_component]
+pub fn GridCanvas(props: &GridCanvasProps) -> Html {
+    let prev_ref = use_node_ref();
+    let next_ref = use_node_ref();
+
+    use_effect_with((props.prev, prev_ref.clone()), |(blocks, canvas)| {
+        draw(canvas, blocks);
+    });
+
+    use_effect_with((props.next, next_ref.clone()), |(blocks, canvas)| {
+        draw(canvas, blocks);
+    });
+
+    let size = GRID_SIZE.to_string();
+    let layer = "absolute inset-0 h-full w-full [image-rendering:pixelated]";
+
+    html! {
+        <div class={classes!("relative", props.class.clone())}>
+            <canvas ref={prev_ref} class={layer} width={size.clone()} height={size.clone()} />
+            <canvas ref={next_ref} class={layer} width={size.clone()} height={size} />
+        </div>
+    }
+}
+
+fn draw(canvas: &NodeRef, blocks: &Blocks) {
+    let Some(canvas) = canvas.cast::<HtmlCanvasElement>() else {
+        return;
+    };
+
+    let context = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok());
+
+    let Some(context) = context else {
+        tracing::error!("could not get 2D canvas context");
+        return;
+    };
+
+    let size = GRID_SIZE as u32;
+    let pixels = blocks.rgba8();
+    let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&pixels), size, size)
+        .expect("pixel data matches canvas size");
+
+    if let Err(err) = context.put_image_data(&image, 0.0, 0.0) {
+        tracing::error!({ ?err }, "could not draw to canvas");
+    }
+}
@@ -4,7 +4,7 @@ use wasm_bindgen_futures::spawn_local;
 use web_sys::Location;
 use yew::prelude::*;
 
-use crate::types::Session;
+use crate::types::{Session, CSRF_TOKEN_HEADER};
 use crate::Route;
 
 type Link = yew_router::components::Link<Route>;
@@ -45,8 +45,6 @@ pub fn Header(props: &HeaderProps) -> Html {
     }
 }
 
-const CSRF_TOKEN_HEADER: &str = "X-Csrf-Token";
-
 async fn log_out(session: Session) {
     let location = document().location().expect("page always has location");
 
@@ -0,0 +1,130 @@
+use gloo::utils::window;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_router::Routable;
+
+use crate::components::*;
+use crate::types::{NewProgram, Program, Session};
+use crate::Route;
+
+type Link = yew_router::components::Link<Route>;
+
+#[derive(Properties, PartialEq, Debug)]
+pub struct ProgramSaveProps {
+    #[prop_or_default]
+    pub class: Classes,
+
+    pub session: Session,
+
+    /// The share link fragment to save.
+    pub share: String,
+
+    /// The slug of the program this was forked from.
+    #[prop_or_default]
+    pub parent: Option<String>,
+
+    pub onclose: Callback<()>,
+}
+
+/// Saves the current program on the server to get a short link for it.
+#[function_component]
+pub fn ProgramSave(props: &ProgramSaveProps) -> Html {
+    let name_ref = use_node_ref();
+    let public_ref = use_node_ref();
+    let saving = use_state(|| false);
+    let result = use_state(|| None::<Result<Program, String>>);
+
+    let onsubmit = {
+        let session = props.session.clone();
+        let share = props.share.clone();
+        let parent = props.parent.clone();
+        let name_ref = name_ref.clone();
+        let public_ref = public_ref.clone();
+        let saving = saving.clone();
+        let result = result.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+
+            let name = name_ref
+                .cast::<HtmlInputElement>()
+                .map(|input| input.value())
+                .unwrap_or_default();
+
+            let public = public_ref
+                .cast::<HtmlInputElement>()
+                .is_some_and(|input| input.checked());
+
+            let new = NewProgram {
+                name,
+                share: share.clone(),
+                public,
+                parent: parent.clone(),
+            };
+
+            let session = session.clone();
+            let saving = saving.clone();
+            let result = result.clone();
+
+            saving.set(true);
+            spawn_local(async move {
+                let res = Program::create(&session, &new).await.map_err(|err| {
+                    tracing::error!({ ?err }, "save program");
+                    err.to_string()
+                });
+
+                result.set(Some(res));
+                saving.set(false);
+            });
+        })
+    };
+
+    let onclose = props.onclose.reform(|_| ());
+
+    let class = classes!("flex", "flex-col", "gap-2", "max-w-prose", props.class.clone());
+
+    html! {
+        <div {class}>
+            if let Some(parent) = &props.parent {
+                <p>
+                    {"This is a fork of "}
+                    <Link to={Route::MosaicProgram { slug: parent.clone() }}>{"another program"}</Link>
+                    {". Saving it will link back to the original."}
+                </p>
+            }
+
+            <form class="flex flex-row flex-wrap items-center gap-2" {onsubmit}>
+                <label for="program_name">{"Name"}</label>
+                <input name="program_name" type="text" required=true maxlength="100" ref={name_ref} />
+                <label>
+                    <input name="program_public" type="checkbox" ref={public_ref} />
+                    {" Show in the gallery"}
+                </label>
+                <button type="submit" disabled={*saving}>{"Save"}</button>
+                <button type="button" onclick={onclose}>{"Close"}</button>
+            </form>
+
+            { match &*result {
+                None => html! {},
+                Some(Ok(program)) => {
+                    let origin = window().location().origin().unwrap_or_default();
+                    let url = format!("{origin}{}", program.route().to_path());
+
+                    html! {
+                        <div class="info">
+                            <p>{"Saved! Anyone with this link can run it:"}</p>
+                            <p><Link to={program.route()}>{url}</Link></p>
+                            <p><Link to={Route::MosaicPrograms}>{"See all my programs"}</Link></p>
+                        </div>
+                    }
+                }
+                Some(Err(err)) => html! {
+                    <Error error={err.clone()}>
+                        <p>{"Could not save the program."}</p>
+                    </Error>
+                },
+            } }
+        </div>
+    }
+}
@@ -0,0 +1,70 @@
+use gloo::timers::callback::Timeout;
+use yew::prelude::*;
+
+use crate::apps::mosaic::{Blocks, Share};
+use crate::components::Frames;
+
+/// How many ticks to run for the preview (after the initial state).
+const THUMBNAIL_TICKS: u64 = 15;
+
+const FRAME_MILLIS: u32 = 250;
+
+#[derive(Properties, PartialEq, Debug)]
+pub struct ProgramThumbnailProps {
+    #[prop_or_default]
+    pub class: Classes,
+
+    /// The share link fragment to preview.
+    pub share: String,
+}
+
+/// A small looping animation of the first few ticks of a program.
+#[function_component]
+pub fn ProgramThumbnail(props: &ProgramThumbnailProps) -> Html {
+    let frames = use_memo(props.share.clone(), |share| frames(share));
+    let index = use_state(|| 0usize);
+
+    let len = (*frames).as_ref().map(Vec::len).unwrap_or_default();
+
+    // Schedule the next frame every time one is shown.
+    use_effect_with((*index, len), {
+        let index = index.clone();
+        move |&(i, len)| {
+            let timeout = (len > 1).then(|| Timeout::new(FRAME_MILLIS, move || index.set((i + 1) % len)));
+            move || drop(timeout)
+        }
+    });
+
+    let class = classes!("box-square", props.class.clone());
+
+    match &*frames {
+        Ok(frames) => {
+            let (prev, next) = frames[*index % frames.len()];
+            html! {
+                <div {class}>
+                    <super::GridCanvas {prev} {next} class="h-full w-full" />
+                </div>
+            }
+        }
+        Err(err) => html! {
+            <div {class} title={err.clone()}>
+                <p class="alert">{"Broken program"}</p>
+            </div>
+        },
+    }
+}
+
+fn frames(share: &str) -> Result<Vec<(Blocks, Blocks)>, String> {
+    let share = Share::try_decode(share).map_err(|err| err.to_string())?;
+    let seed = share.seed.unwrap_or_default();
+
+    // Show what worked so far if the program breaks partway through.
+    let frames = Frames::run(
+        &share.module.binary,
+        seed,
+        share.edges,
+        &share.initial,
+        THUMBNAIL_TICKS,
+    )?;
+    Ok(frames.frames)
+}
@@ -1,9 +1,21 @@
-use gloo::timers::callback::Interval;
+use std::collections::VecDeque;
+
+use gloo::worker::{Spawnable, WorkerBridge};
+use web::simulation_worker::{
+    Frame, SimulationInput, SimulationOutput, SimulationWorker, SCRIPT_PATH,
+};
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
 
-use crate::apps::mosaic::{Blocks, Interpreter, Neighborhood, Rgba, GRID_SIZE};
+use crate::apps::mosaic::{Blocks, EdgeMode, Initial, Rgba, Speed};
 use crate::components::*;
 
+/// How many previous ticks to keep around for stepping backward.
+const HISTORY_LIMIT: usize = 100;
+
+/// The brush sizes to choose from, in blocks on each side.
+const BRUSH_SIZES: [usize; 4] = [1, 2, 3, 5];
+
 #[derive(Properties, PartialEq, Debug)]
 pub struct SimulationProps {
     #[prop_or_default]
@@ -11,101 +23,418 @@ pub struct SimulationProps {
 
     pub seed: u64,
     pub update: Vec<u8>,
+
+    #[prop_or_default]
+    pub edges: EdgeMode,
+
+    #[prop_or_default]
+    pub initial: Initial,
+
+    #[prop_or_default]
+    pub speed: Speed,
+
+    /// Called when the user picks a different speed.
+    #[prop_or_default]
+    pub onspeed: Callback<Speed>,
+
+    /// Called when the user asks to fix a broken module.
+    #[prop_or_default]
+    pub onedit: Callback<()>,
+
+    /// Called with the blocks on screen when the user wants to start from them every time.
+    #[prop_or_default]
+    pub onsaveinitial: Callback<Blocks>,
 }
 
 pub struct Simulation {
-    prev: Blocks,
-    next: Blocks,
+    frame: Frame,
+    history: VecDeque<Frame>,
+
+    /// Why the simulation stopped, if the module is broken.
+    error: Option<String>,
+
+    playing: bool,
+    renderer: Renderer,
+    brush: Brush,
 
-    interpreter: Interpreter,
+    /// The interpreter runs in a worker, which sends back each tick as it finishes.
+    worker: WorkerBridge<SimulationWorker>,
 
-    _interval: Interval,
+    /// Counts the commands sent to the worker, so results from before the latest one (like a tick
+    /// that finished just after pausing) can be ignored.
+    generation: u64,
 }
 
-// TODO: Show error state if broken
+/// What painting on the grid while paused does.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Brush {
+    color: Rgba,
+    size: usize,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            color: Rgba::WHITE,
+            size: 1,
+        }
+    }
+}
+
+/// How to draw the grid.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Renderer {
+    /// One element per block, styled with CSS.
+    #[default]
+    Dom,
+
+    /// One canvas per layer, one pixel per block.
+    Canvas,
+}
+
+impl Renderer {
+    pub const ALL: [Renderer; 2] = [Renderer::Dom, Renderer::Canvas];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Renderer::Dom => "DOM",
+            Renderer::Canvas => "Canvas",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulationMsg {
+    Worker(SimulationOutput),
+    Play,
+    Pause,
+    StepForward,
+    StepBackward,
+    Reset,
+    SetRenderer(Renderer),
+    Paint((usize, usize)),
+    SetColor(Rgba),
+    SetBrushSize(usize),
+    SaveInitial,
+}
 
 impl Component for Simulation {
-    type Message = ();
+    type Message = SimulationMsg;
 
     type Properties = SimulationProps;
 
     fn create(ctx: &Context<Self>) -> Self {
         let props = ctx.props();
-        let link = ctx.link().clone();
 
-        Self {
-            prev: Blocks::default(),
-            next: Blocks::from_seed(props.seed),
+        let link = ctx.link().clone();
+        let worker = SimulationWorker::spawner()
+            .callback(move |output| link.send_message(SimulationMsg::Worker(output)))
+            .spawn(SCRIPT_PATH);
 
-            interpreter: Interpreter::new(&props.update).unwrap(),
+        let mut simulation = Self {
+            frame: initial_frame(props),
+            history: VecDeque::new(),
+            error: None,
+            playing: true,
+            renderer: Renderer::default(),
+            brush: Brush::default(),
+            worker,
+            generation: 0,
+        };
 
-            _interval: Interval::new(1000, move || {
-                link.send_message(());
-            }),
-        }
+        simulation.restart(ctx);
+        simulation
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         let props = ctx.props();
 
+        let class = classes!("flex", "flex-col", props.class.clone());
+
+        let Frame { prev, next, .. } = self.frame;
+
+        // Painting needs one element per block to click on, so it always uses the DOM renderer.
+        // The canvas only matters for keeping up while playing anyway.
+        let onpaint = (!self.playing).then(|| ctx.link().callback(SimulationMsg::Paint));
+
+        let inner = match &self.error {
+            None => html! {
+                <div class="flex-grow flex justify-center items-center min-h-0 container-size">
+                    <div class="box-square">
+                        if self.renderer == Renderer::Canvas && onpaint.is_none() {
+                            <GridCanvas {prev} {next} class="h-full w-full" />
+                        } else {
+                            <Grid {prev} {next} {onpaint} class="h-full w-full" />
+                        }
+                    </div>
+                </div>
+            },
+            Some(err) => {
+                let onclick = props.onedit.reform(|_| ());
+
+                html! {
+                    <div class="flex-grow flex flex-col justify-center items-center space-y-2">
+                        <Error error={err.clone()}>
+                            <p>{"The simulation stopped because the module is broken."}</p>
+                        </Error>
+                        <button type="button" {onclick}>{"Edit module"}</button>
+                    </div>
+                }
+            }
+        };
+
         html! {
-            <Grid prev={self.prev} next={self.next} class={props.class.clone()} />
+            <div {class}>
+                {inner}
+                {self.view_controls(ctx)}
+                if !self.playing && self.error.is_none() {
+                    {self.view_paint_controls(ctx)}
+                }
+
+                <details class="self-center py-1">
+                    <summary>{"Export"}</summary>
+                    <SimulationExport
+                        blocks={next}
+                        seed={props.seed}
+                        edges={props.edges}
+                        initial={props.initial.clone()}
+                        update={props.update.clone()}
+                        speed={props.speed}
+                    />
+                </details>
+            </div>
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
-        // Prevent accidental changes to the Message type.
-        let () = msg;
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            SimulationMsg::Worker(SimulationOutput::Frame { generation, frame }) => {
+                if generation != self.generation {
+                    return false;
+                }
+
+                if self.history.len() == HISTORY_LIMIT {
+                    self.history.pop_front();
+                }
+                self.history.push_back(self.frame);
+                self.frame = *frame;
+            }
 
-        let prev = self.next;
-        self.prev = self.next;
+            SimulationMsg::Worker(SimulationOutput::Error {
+                generation,
+                message,
+            }) => {
+                if generation != self.generation {
+                    return false;
+                }
 
-        // These functions do grid coordinate math with edge wrapping that avoids overflowing
-        // usize. There's probably a smarter way to do this.
+                tracing::error!({ ?message }, "Simulation stopped");
+                self.error = Some(message);
+            }
 
-        fn sub(a: usize, b: usize) -> usize {
-            (a + GRID_SIZE - b) % GRID_SIZE
-        }
+            SimulationMsg::Play => {
+                self.playing = true;
+                self.schedule(ctx);
+            }
 
-        fn add(a: usize, b: usize) -> usize {
-            (a + b) % GRID_SIZE
-        }
+            SimulationMsg::Pause => {
+                self.playing = false;
+                self.schedule(ctx);
+            }
 
-        for r in 0..GRID_SIZE {
-            for c in 0..GRID_SIZE {
-                let neighborhood: Neighborhood = (
-                    prev[(sub(r, 1), sub(c, 1))].into(),
-                    prev[(sub(r, 1), c)].into(),
-                    prev[(sub(r, 1), add(c, 1))].into(),
-                    prev[(r, sub(c, 1))].into(),
-                    prev[(r, c)].into(),
-                    prev[(r, add(c, 1))].into(),
-                    prev[(add(r, 1), sub(c, 1))].into(),
-                    prev[(add(r, 1), c)].into(),
-                    prev[(add(r, 1), add(c, 1))].into(),
-                );
-
-                self.next[(r, c)] = Rgba::from(self.interpreter.eval(neighborhood).unwrap());
+            // Stepping by hand only makes sense while paused.
+            SimulationMsg::StepForward => {
+                self.playing = false;
+                let from = Box::new(self.frame);
+                self.send(|generation| SimulationInput::Step { generation, from });
             }
+
+            SimulationMsg::StepBackward => {
+                self.playing = false;
+                if let Some(frame) = self.history.pop_back() {
+                    self.frame = frame;
+                }
+                self.schedule(ctx);
+            }
+
+            SimulationMsg::Reset => self.restart(ctx),
+
+            SimulationMsg::SetRenderer(renderer) => self.renderer = renderer,
+
+            SimulationMsg::Paint(rc) => {
+                if !self.playing {
+                    let Brush { color, size } = self.brush;
+                    self.frame.next.paint(rc, size, color);
+                    self.frame.states.paint(rc, size, u32::from(color));
+                }
+            }
+
+            SimulationMsg::SetColor(color) => self.brush.color = color,
+            SimulationMsg::SetBrushSize(size) => self.brush.size = size,
+
+            SimulationMsg::SaveInitial => ctx.props().onsaveinitial.emit(self.frame.next),
         }
 
         true
     }
 
     fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
-        // If the update module changes, we have to rebuild the whole wasmi instance.
-        let new_update = &ctx.props().update;
-        if new_update != &old_props.update {
-            self.interpreter = Interpreter::new(new_update).unwrap();
-        }
+        let props = ctx.props();
 
-        // If the seed changes, restart from its initial state.
-        let new_seed = ctx.props().seed;
-        if new_seed != old_props.seed {
-            self.prev = Blocks::default();
-            self.next = Blocks::from_seed(new_seed);
+        // Something about how to run changed, so restart from the initial state.
+        let restart = props.update != old_props.update
+            || props.seed != old_props.seed
+            || props.edges != old_props.edges
+            || props.initial != old_props.initial;
+        if restart {
+            self.restart(ctx);
+        } else if props.speed != old_props.speed {
+            self.schedule(ctx);
         }
 
         true
     }
 }
+
+impl Simulation {
+    fn view_controls(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let link = ctx.link();
+
+        let broken = self.error.is_some();
+
+        let play_pause = if self.playing {
+            let onclick = link.callback(|_| SimulationMsg::Pause);
+            html! { <button type="button" {onclick} disabled={broken}>{"Pause"}</button> }
+        } else {
+            let onclick = link.callback(|_| SimulationMsg::Play);
+            html! { <button type="button" {onclick} disabled={broken}>{"Play"}</button> }
+        };
+
+        let onback = link.callback(|_| SimulationMsg::StepBackward);
+        let onforward = link.callback(|_| SimulationMsg::StepForward);
+        let onreset = link.callback(|_| SimulationMsg::Reset);
+
+        let onspeed = props.onspeed.reform(|e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            Speed::ALL[select.selected_index().max(0) as usize]
+        });
+
+        let onrenderer = link.callback(|e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            SimulationMsg::SetRenderer(Renderer::ALL[select.selected_index().max(0) as usize])
+        });
+
+        html! {
+            <div class="flex flex-row flex-wrap justify-center items-center gap-2 py-1">
+                <button type="button" onclick={onback} disabled={broken || self.history.is_empty()}>
+                    {"Step back"}
+                </button>
+                {play_pause}
+                <button type="button" onclick={onforward} disabled={broken}>{"Step"}</button>
+                <button type="button" onclick={onreset}>{"Reset"}</button>
+
+                <label for="speed">{"Speed"}</label>
+                <select name="speed" onchange={onspeed}>
+                    { for Speed::ALL.iter().map(|speed| html! {
+                        <option selected={*speed == props.speed}>{speed.label()}</option>
+                    }) }
+                </select>
+
+                <label for="renderer">{"Renderer"}</label>
+                <select name="renderer" onchange={onrenderer}>
+                    { for Renderer::ALL.iter().map(|renderer| html! {
+                        <option selected={*renderer == self.renderer}>{renderer.label()}</option>
+                    }) }
+                </select>
+
+                <span class="tabular-nums">{format!("Tick {}", self.frame.tick)}</span>
+            </div>
+        }
+    }
+
+    fn view_paint_controls(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+
+        let oncolor = link.batch_callback(|e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let hex = input.value();
+            let rgb = u32::from_str_radix(hex.trim_start_matches('#'), 16).ok()?;
+            Some(SimulationMsg::SetColor(Rgba::from(rgb << 8 | 0xff)))
+        });
+
+        let onsize = link.callback(|e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            SimulationMsg::SetBrushSize(BRUSH_SIZES[select.selected_index().max(0) as usize])
+        });
+
+        let onsave = link.callback(|_| SimulationMsg::SaveInitial);
+
+        // The color input only deals in opaque colors.
+        let color = format!("#{:06x}", u32::from(self.brush.color) >> 8);
+
+        html! {
+            <div class="flex flex-row flex-wrap justify-center items-center gap-2 py-1">
+                <label for="brush_color">{"Paint"}</label>
+                <input name="brush_color" type="color" value={color} onchange={oncolor} />
+
+                <label for="brush_size">{"Brush"}</label>
+                <select name="brush_size" onchange={onsize}>
+                    { for BRUSH_SIZES.iter().map(|size| html! {
+                        <option selected={*size == self.brush.size}>{format!("{size}×{size}")}</option>
+                    }) }
+                </select>
+
+                <button type="button" onclick={onsave}>{"Save as initial state"}</button>
+            </div>
+        }
+    }
+
+    /// Tells the worker to play or pause to match the current playback state. Playing picks up
+    /// from whatever is on screen, which might have been painted or stepped back to.
+    fn schedule(&mut self, ctx: &Context<Self>) {
+        if !self.playing || self.error.is_some() {
+            self.send(|generation| SimulationInput::Pause { generation });
+            return;
+        }
+
+        let from = Box::new(self.frame);
+        let interval_millis = ctx.props().speed.interval_millis();
+        self.send(|generation| SimulationInput::Play {
+            generation,
+            from,
+            interval_millis,
+        });
+    }
+
+    /// Goes back to the initial state for the current props. This also has the worker rebuild
+    /// the wasmi instance, which clears any error from a previous run.
+    fn restart(&mut self, ctx: &Context<Self>) {
+        let props = ctx.props();
+
+        self.frame = initial_frame(props);
+        self.history.clear();
+        self.error = None;
+
+        let update = props.update.clone();
+        let (seed, edges) = (props.seed, props.edges);
+        self.send(|generation| SimulationInput::Load {
+            generation,
+            update,
+            seed,
+            edges,
+        });
+
+        self.schedule(ctx);
+    }
+
+    /// Sends the worker a command with a new generation number.
+    fn send(&mut self, command: impl FnOnce(u64) -> SimulationInput) {
+        self.generation += 1;
+        self.worker.send(command(self.generation));
+    }
+}
+
+fn initial_frame(props: &SimulationProps) -> Frame {
+    Frame::initial(props.initial.generate(props.seed))
+}
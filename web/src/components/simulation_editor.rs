@@ -1,9 +1,23 @@
 use eyre::OptionExt;
+use gloo::file::callbacks::FileReader;
 use gloo::timers::callback::Timeout;
-use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use gloo::utils::{document, window};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    CanvasRenderingContext2d, HtmlCanvasElement, HtmlInputElement, HtmlSelectElement,
+    HtmlTextAreaElement, ImageBitmap,
+};
 use yew::prelude::*;
 
-use crate::apps::mosaic::{Blocks, Module};
+use crate::apps::mosaic::cases::{self, Case, Outcome};
+use crate::apps::mosaic::check::{self, Diagnostic};
+use crate::apps::mosaic::dsl::Pos;
+use crate::apps::mosaic::initial::DEFAULT_DENSITY;
+use crate::apps::mosaic::share::MAX_SOURCE_LEN;
+use crate::apps::mosaic::{
+    dsl, host, Blocks, EdgeMode, Initial, Interpreter, Language, Module, GRID_SIZE,
+};
 use crate::components::*;
 
 #[derive(Properties, PartialEq, Debug)]
@@ -12,7 +26,17 @@ pub struct SimulationEditorProps {
     pub class: Classes,
 
     pub seed: u64,
-    pub source: String,
+    pub module: Module,
+
+    #[prop_or_default]
+    pub edges: EdgeMode,
+
+    #[prop_or_default]
+    pub initial: Initial,
+
+    /// The module's test cases, in the format [`cases::parse`] reads.
+    #[prop_or_default]
+    pub tests: String,
 
     pub onsubmit: Callback<Option<SimulationEditorValue>>,
 }
@@ -20,22 +44,85 @@ pub struct SimulationEditorProps {
 #[derive(PartialEq, Debug)]
 pub struct SimulationEditorValue {
     pub seed: u64,
+    pub edges: EdgeMode,
+    pub initial: Initial,
     pub module: Module,
+    pub tests: String,
 }
 
 pub struct SimulationEditor {
     seed_ref: NodeRef,
     source_ref: NodeRef,
+    tests_ref: NodeRef,
+
+    edges: EdgeMode,
+    initial: Initial,
+    language: Language,
+
+    /// The last imported image, kept around so switching patterns and back doesn't lose it.
+    image: Option<Box<Blocks>>,
+
+    /// Whether "Image" is picked but nothing has been imported yet.
+    picking_image: bool,
 
-    preview: Blocks,
+    /// The last uploaded binary module, which is what runs when the language is Wasm.
+    upload: Option<Module>,
+    reading: Option<FileReader>,
+
+    /// What was in the editor before switching to Wasm, to put back when switching away.
+    draft: String,
+
+    /// The seed and module to preview. The module is the last version that passed the check, so
+    /// the preview doesn't disappear while typing.
+    preview_seed: u64,
+    preview_update: Option<Vec<u8>>,
     pending_update: Option<Timeout>,
 
+    /// The first problem with the source as of the last check. Submitting is only allowed when
+    /// there's nothing pending and no problem.
+    diagnostic: Option<Diagnostic>,
+    pending_check: Option<Timeout>,
+
+    /// The results of the test cases against the preview module, or the first mistake in how
+    /// they're written.
+    test_results: Result<Vec<(Case, Outcome)>, dsl::Error>,
+
+    /// The number of lines in the source, for the gutter.
+    lines: usize,
+
+    /// Where the cursor is, and the brackets next to it if it's next to one.
+    cursor: Pos,
+    bracket: Option<Bracket>,
+
     error: Option<String>,
 }
 
+/// A bracket at the cursor and where its pair is, if it has one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Bracket {
+    pos: Pos,
+    pair: Option<Pos>,
+}
+
+/// How long to wait after the last keystroke before checking the source again.
+const CHECK_DELAY_MILLIS: u32 = 300;
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum SimulationEditorMsg {
     EditSeed,
+    EditSource,
+    EditTests,
+    Check,
+    MoveCursor,
+    JumpTo(Pos),
+    SetEdges(EdgeMode),
+    SetPattern(usize),
+    SetDensity(u8),
+    ImportImage(web_sys::File),
+    Imported(Result<Box<Blocks>, String>),
+    SetLanguage(Language),
+    Upload(web_sys::File),
+    Uploaded(Result<Vec<u8>, String>),
     UpdatePreview,
     Save,
     Cancel,
@@ -52,10 +139,29 @@ impl Component for SimulationEditor {
         Self {
             seed_ref: NodeRef::default(),
             source_ref: NodeRef::default(),
-            preview: Blocks::default(),
+            tests_ref: NodeRef::default(),
+            edges: ctx.props().edges,
+            initial: ctx.props().initial.clone(),
+            image: match &ctx.props().initial {
+                Initial::Image(image) => Some(image.clone()),
+                _ => None,
+            },
+            picking_image: false,
+            language: ctx.props().module.language,
+            upload: (!ctx.props().module.language.is_text()).then(|| ctx.props().module.clone()),
+            reading: None,
+            draft: String::new(),
+            preview_seed: ctx.props().seed,
+            preview_update: Some(ctx.props().module.binary.clone()),
             pending_update: Some(Timeout::new(0, move || {
                 link.send_message(SimulationEditorMsg::UpdatePreview)
             })),
+            diagnostic: None,
+            pending_check: None,
+            test_results: Ok(Vec::new()),
+            lines: 1 + ctx.props().module.text.lines().count(),
+            cursor: Pos { line: 1, column: 1 },
+            bracket: None,
             error: None,
         }
     }
@@ -64,8 +170,46 @@ impl Component for SimulationEditor {
         let props = ctx.props();
 
         let oninput = ctx.link().callback(|_| SimulationEditorMsg::EditSeed);
+        let onsource = ctx.link().callback(|_| SimulationEditorMsg::EditSource);
+        let ontests = ctx.link().callback(|_| SimulationEditorMsg::EditTests);
+        let onkeyup = ctx
+            .link()
+            .callback(|_: KeyboardEvent| SimulationEditorMsg::MoveCursor);
+        let onclick = ctx
+            .link()
+            .callback(|_: MouseEvent| SimulationEditorMsg::MoveCursor);
         let onsubmit = ctx.link().callback(|_| SimulationEditorMsg::Save);
         let oncancel = ctx.link().callback(|_| SimulationEditorMsg::Cancel);
+        let onedges = ctx.link().callback(|e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            SimulationEditorMsg::SetEdges(EdgeMode::ALL[select.selected_index().max(0) as usize])
+        });
+        let onlanguage = ctx.link().callback(|e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            SimulationEditorMsg::SetLanguage(
+                Language::ALL[select.selected_index().max(0) as usize],
+            )
+        });
+        let onupload = ctx.link().batch_callback(|e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let file = input.files().and_then(|files| files.get(0));
+            file.map(SimulationEditorMsg::Upload)
+        });
+        let onpattern = ctx.link().callback(|e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            SimulationEditorMsg::SetPattern(select.selected_index().max(0) as usize)
+        });
+        let ondensity = ctx.link().callback(|e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let density = input.value().parse().unwrap_or(DEFAULT_DENSITY);
+            SimulationEditorMsg::SetDensity(density.min(100))
+        });
+        let onimage = ctx.link().batch_callback(|e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let file = input.files().and_then(|files| files.get(0));
+            file.map(SimulationEditorMsg::ImportImage)
+        });
+        let showing_image = self.picking_image || matches!(self.initial, Initial::Image(_));
 
         // TODO: Explain the module requirements
         let class = classes!(
@@ -79,7 +223,7 @@ impl Component for SimulationEditor {
         html! {
             <form action="javascript:void(0);" {class}>
                 <div class="max-w-prose h-full flex-col align-start">
-                    {docs()}
+                    {docs(self.language)}
 
                     if let Some(err) = &self.error {
                         <div class="alert"><pre>{err.to_string()}</pre></div>
@@ -87,13 +231,57 @@ impl Component for SimulationEditor {
 
                     <div class="flex flex-row justify-around">
                         <button type="button" onclick={oncancel}>{"Cancel"}</button>
-                        <button type="button" onclick={onsubmit}>{"Submit"}</button>
+                        <button type="button" onclick={onsubmit} disabled={!self.can_submit()}>{"Submit"}</button>
                     </div>
                 </div>
 
                 <div class="flex flex-col">
+                    <div class="space-x-4">
+                        <label for="language">{"Language"}</label>
+                        <select name="language" onchange={onlanguage}>
+                            { for Language::ALL.iter().map(|language| html! {
+                                <option selected={*language == self.language}>{language.label()}</option>
+                            }) }
+                        </select>
+                    </div>
+                    if !self.language.is_text() {
+                        <div class="space-x-4">
+                            <label for="upload">{"Upload"}</label>
+                            <input name="upload" type="file" accept=".wasm,application/wasm" onchange={onupload} />
+                        </div>
+                    }
                     <label for="source">{"Module"}</label>
-                    <textarea name="source" ref={self.source_ref.clone()} />
+                    <div class="flex flex-row font-mono">
+                        {self.gutter()}
+                        <textarea
+                            name="source"
+                            ref={self.source_ref.clone()}
+                            readonly={!self.language.is_text()}
+                            wrap="off"
+                            spellcheck="false"
+                            class="flex-grow leading-normal"
+                            oninput={onsource}
+                            {onkeyup}
+                            {onclick}
+                        />
+                    </div>
+                    {self.status(ctx)}
+
+                    <details class="mt-2" open={!props.tests.trim().is_empty()}>
+                        <summary>{"Tests"}</summary>
+                        <p>{"One case per line: the nine neighborhood values (like "}<code>{"#ff0000"}</code>{" or "}<code>{"0xff0000ff"}</code>{"), then "}<code>{"->"}</code>{" and what "}<code>{"next"}</code>{" should return. Cases run at tick 0 in the upper-left corner."}</p>
+                        <textarea
+                            name="tests"
+                            ref={self.tests_ref.clone()}
+                            rows="4"
+                            wrap="off"
+                            spellcheck="false"
+                            placeholder={cases::EXAMPLE}
+                            class="w-full font-mono leading-normal"
+                            oninput={ontests}
+                        />
+                        {self.test_summary()}
+                    </details>
                 </div>
 
                 <div class="flex flex-col h-full space-y-2">
@@ -102,10 +290,54 @@ impl Component for SimulationEditor {
                         <label for="seed">{"Seed"}</label>
                         <input name="seed" ref={self.seed_ref.clone()} type="text" {oninput} />
                     </div>
-                    <div class="flex flex-grow justify-start items-start h-full min-h-0">
-                        <div class="box-square">
-                            <Grid prev={Blocks::default()} next={self.preview} class="h-full w-full" />
+                    <div class="space-x-4">
+                        <label for="pattern">{"Pattern"}</label>
+                        <select name="pattern" onchange={onpattern}>
+                            { for Initial::PATTERNS.iter().map(|pattern| html! {
+                                <option selected={!showing_image && pattern.same_kind(&self.initial)}>{pattern.label()}</option>
+                            }) }
+                            <option selected={showing_image}>{"Image"}</option>
+                        </select>
+                    </div>
+                    if let Initial::Sparse { density } = self.initial {
+                        <div class="space-x-4">
+                            <label for="density">{"Density (%)"}</label>
+                            <input name="density" type="number" min="0" max="100" value={density.to_string()} onchange={ondensity} />
+                        </div>
+                    }
+                    if showing_image {
+                        <div class="space-x-4">
+                            <label for="image">{"Image"}</label>
+                            <input name="image" type="file" accept="image/*" onchange={onimage} />
                         </div>
+                        <p class="notice">{format!("Images are scaled to {GRID_SIZE}×{GRID_SIZE}, so the seed doesn't change anything.")}</p>
+                    }
+                    <div class="space-x-4">
+                        <label for="edges">{"Edges"}</label>
+                        <select name="edges" onchange={onedges}>
+                            { for EdgeMode::ALL.iter().map(|edges| html! {
+                                <option selected={*edges == self.edges}>{edges.label()}</option>
+                            }) }
+                        </select>
+                    </div>
+                    <div class="flex flex-col flex-grow justify-start items-start h-full min-h-0">
+                        <p>{"Preview"}</p>
+                        if let Some(update) = &self.preview_update {
+                            if self.diagnostic.is_some() {
+                                <p class="notice">{"Showing the last version that worked."}</p>
+                            }
+                            <SimulationPreview
+                                update={update.clone()}
+                                seed={self.preview_seed}
+                                edges={self.edges}
+                                initial={self.initial.clone()}
+                                class="w-full"
+                            />
+                        } else {
+                            <div class="box-square">
+                                <Grid prev={Blocks::default()} next={self.initial.generate(self.preview_seed)} class="h-full w-full" />
+                            </div>
+                        }
                     </div>
                 </div>
             </form>
@@ -115,14 +347,18 @@ impl Component for SimulationEditor {
     fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
         if first_render {
             let props = ctx.props();
-            let rows = 1 + props.source.lines().count() as u32;
+            let rows = 1 + props.module.text.lines().count() as u32;
 
             let seed = self.seed_ref.cast::<HtmlInputElement>().unwrap();
             let source = self.source_ref.cast::<HtmlTextAreaElement>().unwrap();
+            let tests = self.tests_ref.cast::<HtmlTextAreaElement>().unwrap();
 
             seed.set_value(&props.seed.to_string());
-            source.set_value(&props.source);
+            tests.set_value(&props.tests);
+            source.set_value(&props.module.text);
             source.set_rows(rows);
+
+            ctx.link().send_message(SimulationEditorMsg::Check);
         }
     }
 
@@ -139,9 +375,172 @@ impl Component for SimulationEditor {
                 false
             }
 
+            SimulationEditorMsg::EditSource => {
+                let link = ctx.link().clone();
+                self.pending_check = Some(Timeout::new(CHECK_DELAY_MILLIS, move || {
+                    link.send_message(SimulationEditorMsg::Check)
+                }));
+
+                if let Some(source) = self.source_ref.cast::<HtmlTextAreaElement>() {
+                    self.lines = 1 + source.value().lines().count();
+                    source.set_rows(self.lines as u32);
+                }
+                self.update_cursor();
+                true
+            }
+
+            SimulationEditorMsg::EditTests => {
+                self.run_tests();
+                true
+            }
+
+            SimulationEditorMsg::Check => {
+                self.pending_check = None;
+                self.recheck();
+                true
+            }
+
+            SimulationEditorMsg::MoveCursor => self.update_cursor(),
+
+            SimulationEditorMsg::JumpTo(pos) => {
+                if let Some(source) = self.source_ref.cast::<HtmlTextAreaElement>() {
+                    let text = source.value();
+                    let offset = utf16_offset(&text, check::offset_at(&text, pos));
+
+                    let _ = source.focus();
+                    let _ = source.set_selection_range(offset, offset);
+                }
+                self.update_cursor()
+            }
+
+            SimulationEditorMsg::SetEdges(edges) => {
+                self.edges = edges;
+                true
+            }
+
+            SimulationEditorMsg::SetPattern(index) => {
+                match Initial::PATTERNS.get(index) {
+                    Some(pattern) => {
+                        self.picking_image = false;
+                        if !pattern.same_kind(&self.initial) {
+                            self.initial = pattern.clone();
+                        }
+                    }
+                    None => match &self.image {
+                        Some(image) => self.initial = Initial::Image(image.clone()),
+                        None => self.picking_image = true,
+                    },
+                }
+                true
+            }
+
+            SimulationEditorMsg::SetDensity(density) => {
+                self.initial = Initial::Sparse { density };
+                true
+            }
+
+            SimulationEditorMsg::ImportImage(file) => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let res = import_image(file).await;
+                    link.send_message(SimulationEditorMsg::Imported(res));
+                });
+                false
+            }
+
+            SimulationEditorMsg::Imported(res) => {
+                match res {
+                    Ok(image) => {
+                        self.initial = Initial::Image(image.clone());
+                        self.image = Some(image);
+                        self.picking_image = false;
+                        self.error = None;
+                    }
+                    Err(err) => self.error = Some(format!("could not import image: {err}")),
+                }
+                true
+            }
+
+            SimulationEditorMsg::SetLanguage(language) => {
+                if let Some(source) = self.source_ref.cast::<HtmlTextAreaElement>() {
+                    let text = if !language.is_text() {
+                        self.draft = source.value();
+                        self.upload.as_ref().map(|m| m.text.clone()).unwrap_or_default()
+                    } else if self.language.is_text() {
+                        source.value()
+                    } else if let (Some(upload), Language::Wat) = (&self.upload, language) {
+                        // A disassembly is valid WAT, so it's a fine place to start editing.
+                        upload.text.clone()
+                    } else {
+                        std::mem::take(&mut self.draft)
+                    };
+
+                    // Swap in the new language's example, unless there are edits worth keeping.
+                    let text = if language.is_text()
+                        && (text.trim().is_empty() || text.trim() == self.language.example().trim())
+                    {
+                        String::from(language.example())
+                    } else {
+                        text
+                    };
+
+                    source.set_value(&text);
+                    self.lines = 1 + text.lines().count();
+                    source.set_rows(self.lines as u32);
+                }
+
+                self.language = language;
+                self.error = None;
+                self.recheck();
+                self.update_cursor();
+                true
+            }
+
+            SimulationEditorMsg::Upload(file) => {
+                let link = ctx.link().clone();
+                self.reading = Some(gloo::file::callbacks::read_as_bytes(
+                    &file.into(),
+                    move |res| {
+                        let res = res.map_err(|err| err.to_string());
+                        link.send_message(SimulationEditorMsg::Uploaded(res))
+                    },
+                ));
+                false
+            }
+
+            SimulationEditorMsg::Uploaded(res) => {
+                self.reading = None;
+
+                let module = match res {
+                    Ok(bytes) if bytes.len() > MAX_SOURCE_LEN => Err(format!(
+                        "module is {} bytes, but the limit is {MAX_SOURCE_LEN}",
+                        bytes.len()
+                    )),
+                    Ok(bytes) => Module::from_binary(bytes).map_err(|err| err.to_string()),
+                    Err(err) => Err(format!("could not read file: {err}")),
+                };
+
+                match module {
+                    Ok(module) => {
+                        if let Some(source) = self.source_ref.cast::<HtmlTextAreaElement>() {
+                            source.set_value(&module.text);
+                            self.lines = 1 + module.text.lines().count();
+                            source.set_rows(self.lines as u32);
+                        }
+                        self.upload = Some(module);
+                        self.error = None;
+                    }
+                    Err(err) => self.error = Some(err),
+                }
+
+                self.recheck();
+                true
+            }
+
             SimulationEditorMsg::UpdatePreview => {
                 self.pending_update = None;
-                self.preview = Blocks::from_seed(self.current_seed().unwrap_or_default());
+                self.preview_seed = self.current_seed().unwrap_or_default();
+                self.run_tests();
                 true
             }
 
@@ -150,12 +549,18 @@ impl Component for SimulationEditor {
 
                 match self.current_module() {
                     Ok(module) => {
-                        let value = SimulationEditorValue { seed, module };
+                        let value = SimulationEditorValue {
+                            seed,
+                            edges: self.edges,
+                            initial: self.initial.clone(),
+                            module,
+                            tests: self.tests_text(),
+                        };
                         props.onsubmit.emit(Some(value));
                         false
                     }
                     Err(err) => {
-                        self.error = Some(err.to_string());
+                        self.diagnostic = Some(err);
                         true
                     }
                 }
@@ -175,20 +580,312 @@ impl SimulationEditor {
         Ok(seed.value().parse()?)
     }
 
-    fn current_module(&self) -> eyre::Result<Module> {
-        let source = self.source_ref.cast::<HtmlTextAreaElement>();
-        let source = source.ok_or_eyre("no textarea element")?;
-        Ok(Module::new(source.value())?)
+    fn current_module(&self) -> Result<Module, Diagnostic> {
+        if !self.language.is_text() {
+            return self.upload.clone().ok_or_else(|| Diagnostic {
+                pos: None,
+                message: String::from("Upload a .wasm file to run it."),
+            });
+        }
+
+        check::check(self.language, &self.source_text())
+    }
+
+    fn source_text(&self) -> String {
+        self.source_ref
+            .cast::<HtmlTextAreaElement>()
+            .map(|source| source.value())
+            .unwrap_or_default()
+    }
+
+    fn tests_text(&self) -> String {
+        self.tests_ref
+            .cast::<HtmlTextAreaElement>()
+            .map(|tests| tests.value())
+            .unwrap_or_default()
+    }
+
+    /// Checks the source and updates the preview if it passes.
+    fn recheck(&mut self) {
+        match self.current_module() {
+            Ok(module) => {
+                self.diagnostic = None;
+                self.preview_update = Some(module.binary);
+            }
+            Err(diagnostic) => self.diagnostic = Some(diagnostic),
+        }
+        self.run_tests();
+    }
+
+    /// Runs the test cases against the same module as the preview.
+    fn run_tests(&mut self) {
+        let Some(update) = &self.preview_update else {
+            return;
+        };
+
+        self.test_results = cases::parse(&self.tests_text()).map(|cases| {
+            let outcomes = match Interpreter::new(update, self.preview_seed) {
+                Ok(mut interpreter) => cases::run(&mut interpreter, &cases),
+                Err(err) => vec![Outcome::Error(err.to_string()); cases.len()],
+            };
+            cases.into_iter().zip(outcomes).collect()
+        });
+    }
+
+    /// How many test cases passed, and what went wrong with the rest.
+    fn test_summary(&self) -> Html {
+        let results = match &self.test_results {
+            Ok(results) => results,
+            Err(err) => return html! { <div class="alert"><pre>{err.to_string()}</pre></div> },
+        };
+
+        if results.is_empty() {
+            return html! { <p class="notice">{"No test cases yet."}</p> };
+        }
+
+        let passed = results
+            .iter()
+            .filter(|(_, outcome)| *outcome == Outcome::Pass)
+            .count();
+        let summary = format!("{passed} of {} passed", results.len());
+
+        if passed == results.len() {
+            return html! { <p class="info">{summary}</p> };
+        }
+
+        let failures = results.iter().filter_map(|(case, outcome)| {
+            let problem = match outcome {
+                Outcome::Pass => return None,
+                Outcome::Fail { actual } => {
+                    format!("expected #{:08x}, got #{actual:08x}", case.expected)
+                }
+                Outcome::Error(err) => err.clone(),
+            };
+            Some(html! { <li>{format!("Line {}: {problem}", case.line)}</li> })
+        });
+
+        html! {
+            <div class="alert">
+                <p>{summary}</p>
+                <ul class="list-bulleted">{ for failures }</ul>
+            </div>
+        }
+    }
+
+    fn can_submit(&self) -> bool {
+        self.pending_check.is_none() && self.reading.is_none() && self.diagnostic.is_none()
+    }
+
+    /// Finds the cursor position and the brackets around it. Returns whether anything changed.
+    fn update_cursor(&mut self) -> bool {
+        let Some(source) = self.source_ref.cast::<HtmlTextAreaElement>() else {
+            return false;
+        };
+
+        let text = source.value();
+        let selection = source.selection_start().ok().flatten().unwrap_or(0);
+        let offset = byte_offset(&text, selection);
+
+        let cursor = check::pos_at(&text, offset);
+
+        // Like most editors, prefer the bracket after the cursor, then the one before it.
+        let before = text[..offset].char_indices().next_back();
+        let after = text[offset..].chars().next().map(|c| (offset, c));
+        let bracket = [after, before]
+            .into_iter()
+            .flatten()
+            .find(|(_, c)| "()[]{}".contains(*c))
+            .map(|(i, _)| Bracket {
+                pos: check::pos_at(&text, i),
+                pair: check::matching_bracket(self.language, &text, i)
+                    .map(|j| check::pos_at(&text, j)),
+            });
+
+        let changed = (cursor, bracket) != (self.cursor, self.bracket);
+        self.cursor = cursor;
+        self.bracket = bracket;
+        changed
+    }
+
+    /// Line numbers, with the lines that have a problem or a matching bracket highlighted.
+    fn gutter(&self) -> Html {
+        let error_line = self.diagnostic.as_ref().and_then(|d| d.pos).map(|p| p.line);
+        let bracket_lines: Vec<usize> = self
+            .bracket
+            .iter()
+            .flat_map(|b| [Some(b.pos), b.pair])
+            .flatten()
+            .map(|p| p.line)
+            .collect();
+
+        html! {
+            <div class="border border-transparent leading-normal text-right select-none text-gray-500" aria-hidden="true">
+                { for (1..=self.lines).map(|line| {
+                    let class = if error_line == Some(line) {
+                        "px-1 bg-red-200 dark:bg-red-900"
+                    } else if bracket_lines.contains(&line) {
+                        "px-1 bg-blue-200 dark:bg-blue-900"
+                    } else {
+                        "px-1"
+                    };
+                    html! { <div {class}>{line}</div> }
+                }) }
+            </div>
+        }
+    }
+
+    /// The cursor position, bracket matching, and the result of the last check.
+    fn status(&self, ctx: &Context<Self>) -> Html {
+        let Pos { line, column } = self.cursor;
+
+        let bracket = match self.bracket {
+            None => String::new(),
+            Some(Bracket { pair: None, .. }) => String::from(" · unmatched bracket"),
+            Some(Bracket {
+                pair: Some(pair), ..
+            }) => format!(
+                " · matches line {}, column {}",
+                pair.line, pair.column
+            ),
+        };
+
+        let result = if self.pending_check.is_some() || self.reading.is_some() {
+            html! { <p class="notice">{"Checking…"}</p> }
+        } else if let Some(diagnostic) = &self.diagnostic {
+            let jump = diagnostic.pos.map(|pos| {
+                let onclick = ctx.link().callback(move |_| SimulationEditorMsg::JumpTo(pos));
+                html! { <button type="button" {onclick}>{"Go to error"}</button> }
+            });
+
+            html! {
+                <div class="alert space-x-2">
+                    <pre class="inline whitespace-pre-wrap">{diagnostic.to_string()}</pre>
+                    {jump}
+                </div>
+            }
+        } else {
+            html! { <p class="info">{"Ready to run."}</p> }
+        };
+
+        html! { <>
+            <p class="text-sm text-gray-500 mb-1">{format!("Line {line}, column {column}{bracket}")}</p>
+            {result}
+        </> }
+    }
+}
+
+/// Decodes an image file and scales it down to the size of the grid.
+async fn import_image(file: web_sys::File) -> Result<Box<Blocks>, String> {
+    let js_error = |err: JsValue| format!("{err:?}");
+    let size = GRID_SIZE as u32;
+
+    let bitmap = window()
+        .create_image_bitmap_with_blob(&file)
+        .map_err(js_error)?;
+    let bitmap: ImageBitmap = JsFuture::from(bitmap).await.map_err(js_error)?.unchecked_into();
+
+    let canvas: HtmlCanvasElement = document()
+        .create_element("canvas")
+        .map_err(js_error)?
+        .unchecked_into();
+    canvas.set_width(size);
+    canvas.set_height(size);
+
+    let context: CanvasRenderingContext2d = canvas
+        .get_context("2d")
+        .map_err(js_error)?
+        .ok_or("no 2D canvas context")?
+        .unchecked_into();
+
+    context
+        .draw_image_with_image_bitmap_and_dw_and_dh(&bitmap, 0.0, 0.0, size.into(), size.into())
+        .map_err(js_error)?;
+    let pixels = context
+        .get_image_data(0.0, 0.0, size.into(), size.into())
+        .map_err(js_error)?
+        .data();
+
+    let blocks = Blocks::from_rgba8(&pixels).ok_or("unexpected image size")?;
+    Ok(Box::new(blocks))
+}
+
+/// Converts an offset in UTF-16 code units (like the DOM uses) into a byte offset.
+fn byte_offset(text: &str, utf16: u32) -> usize {
+    let mut units = 0;
+    for (i, c) in text.char_indices() {
+        if units >= utf16 as usize {
+            return i;
+        }
+        units += c.len_utf16();
     }
+    text.len()
 }
 
-fn docs() -> Html {
+/// Converts a byte offset into UTF-16 code units (like the DOM uses).
+fn utf16_offset(text: &str, offset: usize) -> u32 {
+    text[..offset].encode_utf16().count() as u32
+}
+
+fn docs(language: Language) -> Html {
+    let language_docs = match language {
+        Language::Wat => wat_docs(),
+        Language::Dsl => dsl_docs(),
+        Language::Wasm => wasm_docs(),
+    };
+
     html! { <>
     <p>{"The "}<code>{"next"}</code>{" function will be called for each cell in the grid to populate the grid for each tick."}</p>
     <p>{"The parameters are the cell's neighborhood values from the previous tick in row-major order. For example, "}<code>{"$p00"}</code>{" is the upper-left cell, "}<code>{"$p22"}</code>{" is the lower-right, and "}<code>{"$p11"}</code>{" is the value of the current cell."}</p>
 
     <p>{"The bits of each "}<code>{"i32"}</code>{" are packed as RGBA (8 bits for each channel)."}</p>
 
-    <p>{"This WebAssembly Text format (WAT) isn't really meant for authoring code, but it "}<em>{"is"}</em>{" described in "}<a href="https://webassembly.github.io/spec/core/text/index.html">{"the spec"}</a>{"."}</p>
+    <details>
+        <summary>{"Keeping state"}</summary>
+        <p>{"A module that needs to remember more than a color (like how old a cell is) can give "}<code>{"next"}</code>{" eighteen params instead: the nine colors, then the same nine cells' states. It returns the cell's new state, and the module also exports "}<code>{"color"}</code>{", which takes a state and returns the color to show for it. Every cell's state starts out as its initial color."}</p>
+        <p><code>{r#"(func (export "color") (param $state i32) (result i32) ...)"#}</code></p>
+    </details>
+
+    <details>
+        <summary>{"Host functions"}</summary>
+        <p>{"Modules can import these from the "}<code>{"host"}</code>{" namespace. Every parameter and result is an "}<code>{"i32"}</code>{". For example: "}<code>{r#"(import "host" "hsv" (func $hsv (param i32 i32 i32) (result i32)))"#}</code></p>
+        <dl>
+            { for host::DOCS.iter().map(|(signature, description)| html! { <>
+                <dt><code>{signature}</code></dt>
+                <dd>{description}</dd>
+            </> }) }
+        </dl>
+    </details>
+
+    {language_docs}
     </> }
 }
+
+fn wat_docs() -> Html {
+    html! {
+    <p>{"This WebAssembly Text format (WAT) isn't really meant for authoring code, but it "}<em>{"is"}</em>{" described in "}<a href="https://webassembly.github.io/spec/core/text/index.html">{"the spec"}</a>{"."}</p>
+    }
+}
+
+fn wasm_docs() -> Html {
+    html! {
+    <p>{"Upload a "}<code>{".wasm"}</code>{" file compiled from any language (like Rust, AssemblyScript, or Zig) that exports "}<code>{"next"}</code>{" with nine "}<code>{"i32"}</code>{" params (or eighteen, along with "}<code>{"color"}</code>{") and an "}<code>{"i32"}</code>{" result. It can only import host functions. The disassembly is shown for reference, and switching to WAT makes it editable."}</p>
+    }
+}
+
+fn dsl_docs() -> Html {
+    html! {
+    <details open=true>
+        <summary>{"Expressions"}</summary>
+        <p>{"A rule is any number of "}<code>{"let name = expr"}</code>{" lines followed by "}<code>{"next = expr"}</code>{". The neighborhood is "}<code>{"p00"}</code>{" through "}<code>{"p22"}</code>{"."}</p>
+        <ul class="list-bulleted">
+            <li>{"Numbers can be decimal, hex ("}<code>{"0xff0000ff"}</code>{"), or colors ("}<code>{"#ff0000"}</code>{" or "}<code>{"#ff000080"}</code>{")."}</li>
+            <li><code>{"+ - * / % & | ^ << >> ~"}</code>{" work on the whole value."}</li>
+            <li><code>{".+ .- .*"}</code>{" work on each color channel separately, and "}<code>{"c.r c.g c.b c.a"}</code>{" pick out one channel."}</li>
+            <li><code>{"== != < <= > >= && || !"}</code>{" produce 1 or 0."}</li>
+            <li><code>{"if cond { a } else { b }"}</code>{" picks a value. Any nonzero condition is true."}</li>
+            <li>{"Host functions are called by name, like "}<code>{"hsv(tick() % 360, 255, 255)"}</code>{"."}</li>
+            <li><code>{"//"}</code>{" starts a comment."}</li>
+        </ul>
+    </details>
+    }
+}
@@ -0,0 +1,134 @@
+use gloo::file::{Blob, ObjectUrl};
+use gloo::timers::callback::Timeout;
+use gloo::utils::document;
+use web_sys::wasm_bindgen::JsCast;
+use web_sys::{HtmlElement, HtmlInputElement};
+use yew::prelude::*;
+
+use crate::apps::mosaic::export::{self, Recording, MAX_SCALE, MAX_TICKS};
+use crate::apps::mosaic::{Blocks, EdgeMode, Initial, Speed};
+use crate::components::*;
+
+#[derive(Properties, PartialEq, Debug)]
+pub struct SimulationExportProps {
+    #[prop_or_default]
+    pub class: Classes,
+
+    /// The blocks currently on screen, for saving a single frame.
+    pub blocks: Blocks,
+
+    pub seed: u64,
+    pub edges: EdgeMode,
+    pub initial: Initial,
+    pub update: Vec<u8>,
+    pub speed: Speed,
+}
+
+#[function_component]
+pub fn SimulationExport(props: &SimulationExportProps) -> Html {
+    let scale = use_state(|| 16u32);
+    let ticks = use_state(|| 60u64);
+    let error = use_state(|| None::<String>);
+
+    let onscale = {
+        let scale = scale.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            scale.set(input.value().parse().unwrap_or(1));
+        })
+    };
+
+    let onticks = {
+        let ticks = ticks.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            ticks.set(input.value().parse().unwrap_or(0));
+        })
+    };
+
+    let onpng = {
+        let blocks = props.blocks;
+        let scale = *scale;
+        let error = error.clone();
+        Callback::from(move |_| {
+            let res = export::png(&blocks, scale);
+            error.set(save(res, "mosaic.png", "image/png").err());
+        })
+    };
+
+    let ongif = {
+        let update = props.update.clone();
+        let recording = Recording {
+            seed: props.seed,
+            edges: props.edges,
+            initial: props.initial.clone(),
+            ticks: *ticks,
+            scale: *scale,
+            frame_millis: props.speed.interval_millis(),
+        };
+        let error = error.clone();
+        Callback::from(move |_| {
+            let res = export::gif(&update, recording.clone());
+            error.set(save(res, "mosaic.gif", "image/gif").err());
+        })
+    };
+
+    html! {
+        <div class={classes!("flex", "flex-row", "flex-wrap", "items-center", "gap-2", props.class.clone())}>
+            <label for="export_scale">{"Pixels per block"}</label>
+            <input
+                name="export_scale"
+                type="number"
+                min="1"
+                max={MAX_SCALE.to_string()}
+                value={scale.to_string()}
+                onchange={onscale}
+            />
+            <button type="button" onclick={onpng}>{"Save PNG"}</button>
+
+            <label for="export_ticks">{"Ticks"}</label>
+            <input
+                name="export_ticks"
+                type="number"
+                min="0"
+                max={MAX_TICKS.to_string()}
+                value={ticks.to_string()}
+                onchange={onticks}
+            />
+            <button type="button" onclick={ongif}>{"Record GIF"}</button>
+
+            if let Some(err) = &*error {
+                <Error error={err.clone()} />
+            }
+        </div>
+    }
+}
+
+/// Downloads the exported bytes as a file, or returns a message explaining why it couldn't.
+fn save(
+    res: Result<Vec<u8>, export::ExportError>,
+    filename: &str,
+    mime_type: &str,
+) -> Result<(), String> {
+    let bytes = res.map_err(|err| err.to_string())?;
+
+    let blob = Blob::new_with_options(&bytes[..], Some(mime_type));
+    let url = ObjectUrl::from(blob);
+
+    let link = document()
+        .create_element("a")
+        .ok()
+        .and_then(|el| el.dyn_into::<HtmlElement>().ok())
+        .ok_or_else(|| String::from("could not create download link"))?;
+
+    link.set_attribute("href", &url)
+        .and_then(|()| link.set_attribute("download", filename))
+        .map_err(|err| format!("could not set up download link: {err:?}"))?;
+
+    link.click();
+
+    // The URL is revoked when it's dropped, so give the browser a moment to start the download.
+    Timeout::new(1000, move || drop(url)).forget();
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use gloo::timers::callback::Timeout;
+use yew::prelude::*;
+
+use crate::apps::mosaic::{Blocks, EdgeMode, Initial, Runner};
+use crate::components::*;
+
+/// How many ticks to animate (after the initial state).
+const PREVIEW_TICKS: u64 = 8;
+
+const FRAME_MILLIS: u32 = 400;
+
+#[derive(Properties, PartialEq, Debug)]
+pub struct SimulationPreviewProps {
+    #[prop_or_default]
+    pub class: Classes,
+
+    pub update: Vec<u8>,
+    pub seed: u64,
+
+    #[prop_or_default]
+    pub edges: EdgeMode,
+
+    #[prop_or_default]
+    pub initial: Initial,
+}
+
+/// The first two ticks of a module side by side, with a loop of the next few below them.
+#[function_component]
+pub fn SimulationPreview(props: &SimulationPreviewProps) -> Html {
+    let frames = use_memo(
+        (
+            props.update.clone(),
+            props.seed,
+            props.edges,
+            props.initial.clone(),
+        ),
+        |(update, seed, edges, initial)| {
+            Frames::run(update, *seed, *edges, initial, PREVIEW_TICKS)
+        },
+    );
+    let index = use_state(|| 0usize);
+
+    let len = (*frames).as_ref().map(|f| f.frames.len()).unwrap_or_default();
+
+    // Schedule the next frame every time one is shown.
+    use_effect_with((*index, len), {
+        let index = index.clone();
+        move |&(i, len)| {
+            let timeout = (len > 1).then(|| Timeout::new(FRAME_MILLIS, move || index.set((i + 1) % len)));
+            move || drop(timeout)
+        }
+    });
+
+    let class = classes!("flex", "flex-col", "space-y-2", props.class.clone());
+
+    let frames = match &*frames {
+        Ok(frames) => frames,
+        Err(err) => {
+            return html! {
+                <div {class}>
+                    <div class="box-square">
+                        <Grid prev={Blocks::default()} next={props.initial.generate(props.seed)} class="h-full w-full" />
+                    </div>
+                    <p class="alert">{err}</p>
+                </div>
+            };
+        }
+    };
+
+    let (_, tick_0) = frames.frames[0];
+    let tick_1 = frames.frames.get(1).copied();
+    let i = *index % frames.frames.len();
+    let (prev, next) = frames.frames[i];
+
+    html! {
+        <div {class}>
+            <div class="grid grid-cols-2 gap-2">
+                <figure>
+                    <div class="box-square">
+                        <Grid prev={Blocks::default()} next={tick_0} class="h-full w-full" />
+                    </div>
+                    <figcaption>{"Tick 0"}</figcaption>
+                </figure>
+                <figure>
+                    if let Some((prev, next)) = tick_1 {
+                        <div class="box-square">
+                            <Grid {prev} {next} class="h-full w-full" />
+                        </div>
+                    }
+                    <figcaption>{"Tick 1"}</figcaption>
+                </figure>
+            </div>
+
+            if let Some(err) = &frames.error {
+                <p class="alert">{err}</p>
+            }
+
+            if frames.frames.len() > 2 {
+                <figure class="w-1/2">
+                    <div class="box-square">
+                        <GridCanvas {prev} {next} class="h-full w-full" />
+                    </div>
+                    <figcaption>{format!("Tick {i} of {}", frames.frames.len() - 1)}</figcaption>
+                </figure>
+            }
+        </div>
+    }
+}
+
+/// The first few ticks of a module, each with the tick before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frames {
+    pub frames: Vec<(Blocks, Blocks)>,
+
+    /// Why the module stopped early, if it did.
+    pub error: Option<String>,
+}
+
+impl Frames {
+    /// Runs the module from its initial state. This only fails if the module can't start at all.
+    pub fn run(
+        update: &[u8],
+        seed: u64,
+        edges: EdgeMode,
+        initial: &Initial,
+        ticks: u64,
+    ) -> Result<Self, String> {
+        let mut runner = Runner::new(update, seed)
+            .map_err(|err| err.to_string())?
+            .with_edges(edges)
+            .with_initial(initial);
+
+        let mut frames = vec![(*runner.prev(), *runner.next())];
+        for _ in 0..ticks {
+            if let Err(err) = runner.step() {
+                return Ok(Self {
+                    frames,
+                    error: Some(err.to_string()),
+                });
+            }
+            frames.push((*runner.prev(), *runner.next()));
+        }
+
+        Ok(Self {
+            frames,
+            error: None,
+        })
+    }
+}
@@ -3,9 +3,11 @@ use std::rc::Rc;
 use gloo::storage::errors::StorageError;
 use gloo::storage::{LocalStorage, Storage};
 use uuid::Uuid;
+use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 
-use crate::apps::trellis::{Config, Data};
+use crate::apps::trellis::{Config, Data, LegacySecrets};
+use crate::types::{Session, TrellisSecrets};
 
 const LOCAL_STORAGE_KEY: &str = "trellis.config";
 
@@ -20,6 +22,29 @@ pub struct TrellisConfigProviderProps {
 #[function_component]
 pub fn TrellisConfigProvider(props: &TrellisConfigProviderProps) -> Html {
     let config = use_reducer(TrellisConfig::load);
+    let session = use_context::<Option<Session>>().unwrap();
+
+    {
+        let config = config.clone();
+        use_effect_with(session, move |session| {
+            let legacy_key = config
+                .inner
+                .as_ref()
+                .ok()
+                .and_then(|c| c.legacy_secrets.open_weather.clone());
+
+            if let (Some(session), Some(key)) = (session.clone(), legacy_key) {
+                spawn_local(async move {
+                    match TrellisSecrets::upload_legacy_open_weather(&session, &key).await {
+                        Ok(_) => config.dispatch(TrellisConfigAction::ForgetLegacySecrets),
+                        Err(err) => {
+                            tracing::error!({ ?err }, "upload OpenWeatherMap key from the browser")
+                        }
+                    }
+                });
+            }
+        });
+    }
 
     html! {
         <ContextProvider<TrellisConfigContext> context={config}>
@@ -55,6 +80,9 @@ impl TrellisConfig {
 pub enum TrellisConfigAction {
     Save(Config),
     Update { id: Uuid, data: Data },
+
+    /// Drops keys saved in the browser, once they're on the server.
+    ForgetLegacySecrets,
 }
 
 impl Reducible for TrellisConfig {
@@ -84,6 +112,18 @@ impl Reducible for TrellisConfig {
                 LocalStorage::set(LOCAL_STORAGE_KEY, &config).unwrap();
                 Rc::new(Self { inner: Ok(config) })
             }
+
+            TrellisConfigAction::ForgetLegacySecrets => {
+                let Ok(config) = &self.inner else {
+                    return self;
+                };
+
+                let mut config = config.clone();
+                config.legacy_secrets = LegacySecrets::default();
+
+                LocalStorage::set(LOCAL_STORAGE_KEY, &config).unwrap();
+                Rc::new(Self { inner: Ok(config) })
+            }
         }
     }
 }
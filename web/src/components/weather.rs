@@ -1,46 +1,62 @@
 use gloo::net::http::Request;
 use gloo::timers::callback::Interval;
 use once_cell::sync::Lazy;
+use http::StatusCode;
 use serde::Deserialize;
-use time::OffsetDateTime;
+use time::{Date, OffsetDateTime};
 use url::Url;
 use yew::prelude::*;
 
-static OWM_WEATHER_ENDPOINT: Lazy<Url> = Lazy::new(|| {
-    Url::parse("https://api.openweathermap.org/data/2.5/weather").expect("static URL")
-});
-static OWM_ICON_URL: Lazy<Url> =
-    Lazy::new(|| Url::parse("https://openweathermap.org/img/w/").expect("static URL"));
+use crate::apps::trellis;
+use crate::types::check;
+
+/// The server fetches from the tile's provider (with the user's saved API key, if it needs one) and
+/// caches the result.
+const WEATHER_ENDPOINT: &str = "/api/trellis/weather";
+
 static OWM_CITY_URL: Lazy<Url> =
     Lazy::new(|| Url::parse("https://openweathermap.org/city/").expect("static URL"));
+const OPEN_METEO_URL: &str = "https://open-meteo.com/";
 
 #[derive(Properties, PartialEq, Clone)]
 pub struct WeatherProps {
-    #[prop_or_default]
-    pub location_id: AttrValue,
-    #[prop_or_default]
-    pub owm_api_key: AttrValue,
+    pub settings: trellis::Weather,
 }
 
 pub struct Weather {
     location: Location,
     last_updated_at: Option<OffsetDateTime>,
+    fetching: bool,
     error: Option<WeatherError>,
 
     _interval: Interval,
 }
 
 #[derive(Clone, Default)]
-struct Location {
+pub struct Location {
     name: String,
+    conditions: Vec<Condition>,
+    temperature: String,
+    forecast: Vec<Day>,
+}
+
+#[derive(Clone, Deserialize)]
+struct Condition {
     icon: String,
     description: String,
-    temperature: String,
+}
+
+#[derive(Clone)]
+struct Day {
+    date: Date,
+    low: String,
+    high: String,
+    condition: Option<Condition>,
 }
 
 pub enum WeatherMsg {
     Fetch,
-    Render(Result<OwmResponse, WeatherError>),
+    Render(Result<Box<Location>, WeatherError>),
 }
 
 impl Component for Weather {
@@ -60,50 +76,44 @@ impl Component for Weather {
         Self {
             location: Location::default(),
             last_updated_at: None,
+            fetching: false,
             error: None,
 
             _interval: interval,
         }
     }
 
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        ctx.link().send_message(WeatherMsg::Fetch);
+        true
+    }
+
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             WeatherMsg::Fetch => {
                 let props = ctx.props().clone();
                 ctx.link().send_future(async {
                     let res = fetch_weather(props).await;
-                    WeatherMsg::Render(res)
+                    WeatherMsg::Render(res.map(Box::new))
                 });
 
-                // TODO: Show a "currently fetching" indicator
-                false
+                self.fetching = true;
+                true
             }
 
             WeatherMsg::Render(res) => {
-                let data = match res {
-                    Ok(data) => data,
+                self.fetching = false;
+
+                match res {
+                    Ok(location) => {
+                        self.location = *location;
+                        self.error = None;
+                    }
                     Err(err) => {
                         self.error = Some(err);
                         return true;
                     }
-                };
-
-                // The weather comes back as an array. This is probably one item per
-                // weather station, so taking the first one should be fine for now.
-                //
-                // TODO: Figure out how to handle this list better.
-                let weather = &data.weather[0];
-                let icon_url = OWM_ICON_URL
-                    .join(&format!("{}.png", weather.icon))
-                    .expect("icon name is always a valid path component");
-
-                self.location = Location {
-                    name: data.name,
-                    temperature: format!("{:.0}℉", data.main.temp),
-                    description: weather.description.clone(),
-                    icon: icon_url.to_string(),
-                };
-                self.error = None;
+                }
 
                 let now = OffsetDateTime::now_local().unwrap();
                 self.last_updated_at = Some(now);
@@ -128,7 +138,7 @@ impl Weather {
                 <div class="text-xl text-red-500">
                     {self.error.as_ref().map_or(String::new(), |e| e.to_string())}
                 </div>
-                <p>{"Loading..."}</p>
+                <p>{if self.fetching { "Loading..." } else { "" }}</p>
             </div>
         }
     }
@@ -142,30 +152,57 @@ impl Weather {
             .last_updated_at
             .map_or(String::from("never"), |t| t.format(time).unwrap());
 
-        let owm_url = OWM_CITY_URL
-            .join(&props.location_id)
-            .expect("location ID is always a valid path segment")
-            .to_string();
+        let settings = &props.settings;
+        let attribution = match settings.provider {
+            trellis::Provider::OpenWeatherMap => {
+                let location_id = settings.location_id.as_deref().unwrap_or_default();
+                OWM_CITY_URL
+                    .join(location_id)
+                    .expect("location ID is always a valid path segment")
+                    .to_string()
+            }
+            trellis::Provider::OpenMeteo => OPEN_METEO_URL.to_owned(),
+        };
 
-        let location = self.location.clone();
-        let name = location.name;
-        let icon = location.icon;
-        let description = location.description;
-        let temperature = location.temperature;
+        let location = &self.location;
+
+        let description = if location.conditions.is_empty() {
+            String::from("No conditions reported")
+        } else {
+            let descriptions: Vec<_> = location
+                .conditions
+                .iter()
+                .map(|c| c.description.as_str())
+                .collect();
+            descriptions.join(", ")
+        };
 
         html! {
             <div class="flex flex-col items-center justify-around w-full h-full">
-                <span class="text-3xl">{name}</span>
-                <img src={icon} alt={description.clone()} />
+                <span class="text-3xl">{&location.name}</span>
+                <div class="flex flex-row">
+                    { for location.conditions.iter().map(|c| html! {
+                        <img src={c.icon.clone()} alt={c.description.clone()} />
+                    }) }
+                </div>
                 <span class="text-2xl">{description}</span>
-                <div class="text-2xl">{temperature}</div>
+                <div class="text-2xl">{&location.temperature}</div>
+                if !location.forecast.is_empty() {
+                    <div class="flex flex-row justify-around self-stretch">
+                        { for location.forecast.iter().map(view_day) }
+                    </div>
+                }
                 <div class="text-xl text-red-500">
                     {self.error.as_ref().map_or(String::new(), |e| e.to_string())}
                 </div>
                 <div class="flex items-center justify-around self-stretch text-gray-400">
-                    <a href={owm_url}>{"OpenWeatherMap"}</a>
+                    <a href={attribution}>{settings.provider.label()}</a>
                     <div>
-                        {format!("Last updated at: {}", last_updated)}
+                        if self.fetching {
+                            {"Updating..."}
+                        } else {
+                            {format!("Last updated at: {}", last_updated)}
+                        }
                     </div>
                 </div>
             </div>
@@ -173,53 +210,99 @@ impl Weather {
     }
 }
 
-#[derive(Deserialize, Clone)]
-pub struct OwmResponse {
-    weather: Vec<OwmWeather>,
-    main: OwmMain,
-    name: String,
+fn view_day(day: &Day) -> Html {
+    use time::macros::format_description;
+
+    let weekday = format_description!("[weekday repr:short]");
+
+    html! {
+        <div class="flex flex-col items-center">
+            <span>{day.date.format(weekday).unwrap()}</span>
+            if let Some(condition) = &day.condition {
+                <img src={condition.icon.clone()} alt={condition.description.clone()} title={condition.description.clone()} />
+            }
+            <span>{&day.high}</span>
+            <span class="text-gray-400">{&day.low}</span>
+        </div>
+    }
 }
 
-#[derive(Deserialize, Clone)]
-pub struct OwmWeather {
-    description: String,
-    icon: String,
+/// The server's report, the same shape whichever provider it came from.
+#[derive(Deserialize)]
+struct Report {
+    name: String,
+    conditions: Vec<Condition>,
+    temperature: f64,
+    forecast: Vec<ReportDay>,
 }
 
-#[derive(Deserialize, Clone)]
-pub struct OwmMain {
-    temp: f64,
+#[derive(Deserialize)]
+struct ReportDay {
+    /// In `YYYY-MM-DD` form.
+    date: String,
+    low: f64,
+    high: f64,
+    condition: Option<Condition>,
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum WeatherError {
-    #[error("missing OpenWeatherMap API key")]
-    MissingApiKey,
+    #[error("log in to see the weather")]
+    LoggedOut,
 
-    #[error("missing location ID")]
+    #[error("missing location")]
     MissingLocation,
 
     #[error(transparent)]
     Fetch(#[from] gloo::net::Error),
+
+    #[error(transparent)]
+    Server(#[from] eyre::Report),
 }
 
-async fn fetch_weather(props: WeatherProps) -> Result<OwmResponse, WeatherError> {
-    if props.owm_api_key.is_empty() {
-        return Err(WeatherError::MissingApiKey);
-    }
-    if props.location_id.is_empty() {
-        return Err(WeatherError::MissingLocation);
+async fn fetch_weather(props: WeatherProps) -> Result<Location, WeatherError> {
+    use time::macros::format_description;
+
+    let settings = &props.settings;
+    let location = settings.location().ok_or(WeatherError::MissingLocation)?;
+    let forecast_days = settings.forecast_days.to_string();
+
+    let query = [
+        ("provider", settings.provider.param()),
+        ("location", location),
+        ("units", settings.units.param()),
+        ("lang", settings.lang.as_deref().unwrap_or("en")),
+        ("forecast_days", &forecast_days),
+    ];
+
+    let res = Request::get(WEATHER_ENDPOINT).query(query).send().await?;
+
+    if res.status() == StatusCode::UNAUTHORIZED {
+        return Err(WeatherError::LoggedOut);
     }
 
-    let res = Request::get(OWM_WEATHER_ENDPOINT.as_str())
-        .query([
-            ("id", props.location_id.as_str()),
-            ("appid", props.owm_api_key.as_str()),
-            ("units", "imperial"),
-        ])
-        .send()
-        .await?;
-
-    let weather = res.json().await?;
-    Ok(weather)
+    let report: Report = check(res).await?.json().await?;
+
+    let symbol = settings.units.symbol();
+    let date = format_description!("[year]-[month]-[day]");
+
+    let forecast = report
+        .forecast
+        .into_iter()
+        .filter_map(|day| {
+            Some(Day {
+                date: Date::parse(&day.date, date).ok()?,
+                low: format!("{:.0}{symbol}", day.low),
+                high: format!("{:.0}{symbol}", day.high),
+                condition: day.condition,
+            })
+        })
+        .collect();
+
+    Ok(Location {
+        name: report.name,
+        conditions: report.conditions,
+        temperature: format!("{:.0}{symbol}", report.temperature),
+        forecast,
+    })
 }
@@ -0,0 +1,120 @@
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::components::*;
+use crate::types::{Session, TrellisSecrets};
+
+/// Saves the OpenWeatherMap API key for OpenWeatherMap weather tiles on the server, which fetches
+/// the weather with it. Open-Meteo tiles don't need one. The key is never shown again after it's saved.
+#[function_component]
+pub fn WeatherKey() -> Html {
+    let session = use_context::<Option<Session>>().unwrap();
+
+    match session {
+        Some(session) => html! { <WeatherKeyForm {session} /> },
+        None => html! {
+            <p>{"Log in to save an OpenWeatherMap API key for weather tiles."}</p>
+        },
+    }
+}
+
+#[derive(Properties, PartialEq, Debug)]
+struct WeatherKeyFormProps {
+    session: Session,
+}
+
+#[function_component]
+fn WeatherKeyForm(props: &WeatherKeyFormProps) -> Html {
+    let key_ref = use_node_ref();
+    let secrets = use_state(|| None::<TrellisSecrets>);
+    let saving = use_state(|| false);
+    let error = use_state(|| None::<String>);
+
+    {
+        let secrets = secrets.clone();
+        let error = error.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                match TrellisSecrets::load().await {
+                    Ok(loaded) => secrets.set(Some(loaded)),
+                    Err(err) => error.set(Some(err.to_string())),
+                }
+            });
+        });
+    }
+
+    let save = {
+        let session = props.session.clone();
+        let secrets = secrets.clone();
+        let saving = saving.clone();
+        let error = error.clone();
+
+        Callback::from(move |key: Option<String>| {
+            let session = session.clone();
+            let secrets = secrets.clone();
+            let saving = saving.clone();
+            let error = error.clone();
+
+            saving.set(true);
+            spawn_local(async move {
+                match TrellisSecrets::save_open_weather(&session, key.as_deref()).await {
+                    Ok(saved) => {
+                        secrets.set(Some(saved));
+                        error.set(None);
+                    }
+                    Err(err) => {
+                        tracing::error!({ ?err }, "save OpenWeatherMap key");
+                        error.set(Some(err.to_string()));
+                    }
+                }
+                saving.set(false);
+            });
+        })
+    };
+
+    let onsubmit = {
+        let key_ref = key_ref.clone();
+        let save = save.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+
+            let Some(input) = key_ref.cast::<HtmlInputElement>() else {
+                return;
+            };
+            let key = input.value();
+            input.set_value("");
+
+            save.emit(Some(key));
+        })
+    };
+    let onforget = save.reform(|_| None);
+
+    let saved = secrets.as_ref().is_some_and(|s| s.open_weather);
+
+    html! {
+        <div class="flex flex-col gap-1">
+            <form class="flex flex-row flex-wrap items-center gap-2" {onsubmit}>
+                <label for="open_weather">{"OpenWeatherMap API key"}</label>
+                <input name="open_weather" type="password" required=true autocomplete="off" ref={key_ref} />
+                <button type="submit" disabled={*saving}>{if saved { "Replace" } else { "Save" }}</button>
+                if saved {
+                    <button type="button" disabled={*saving} onclick={onforget}>{"Forget"}</button>
+                }
+            </form>
+            <p class="text-gray-400">
+                { match &*secrets {
+                    None => "Checking for a saved key...",
+                    Some(TrellisSecrets { open_weather: true }) => "A key is saved. OpenWeatherMap tiles fetch through the server with it.",
+                    Some(TrellisSecrets { open_weather: false }) => "No key saved yet, so OpenWeatherMap tiles can't load. Open-Meteo tiles don't need one.",
+                } }
+            </p>
+            if let Some(err) = &*error {
+                <Error error={err.clone()}>
+                    <p>{"Could not load or save the key."}</p>
+                </Error>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,4 @@
+//! The parts of the app that also run in web workers, which are separate binaries that can't
+//! see the rest of it.
+
+pub mod simulation_worker;
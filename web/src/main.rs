@@ -49,6 +49,15 @@ enum Route {
     #[at("/mosaic")]
     Mosaic,
 
+    #[at("/mosaic/p/:slug")]
+    MosaicProgram { slug: String },
+
+    #[at("/mosaic/programs")]
+    MosaicPrograms,
+
+    #[at("/mosaic/gallery")]
+    MosaicGallery,
+
     #[at("/trellis")]
     Trellis,
 
@@ -62,6 +71,9 @@ impl Route {
             Route::NotFound => html! { <pages::NotFound /> },
             Route::Home => html! { <pages::Home /> },
             Route::Mosaic => html! { <pages::Mosaic /> },
+            Route::MosaicProgram { slug } => html! { <pages::MosaicProgram {slug} /> },
+            Route::MosaicPrograms => html! { <pages::MosaicPrograms /> },
+            Route::MosaicGallery => html! { <pages::MosaicGallery /> },
             Route::Trellis => html! {
                 <components::TrellisConfigProvider>
                     <pages::Trellis />
@@ -7,6 +7,9 @@ macro_rules! page {
 
 page!(home);
 page!(mosaic);
+page!(mosaic_gallery);
+page!(mosaic_program);
+page!(mosaic_programs);
 page!(not_found);
 page!(trellis);
 page!(trellis_config);
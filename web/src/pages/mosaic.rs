@@ -4,14 +4,35 @@ use serde::{Deserialize, Serialize};
 use yew::prelude::*;
 use yew_router::prelude::*;
 
-use crate::apps::mosaic::Module;
+use crate::apps::mosaic::{Blocks, Initial, Share, Speed};
 use crate::components::*;
 use crate::hooks::*;
+use crate::types::Session;
 use crate::Route;
 
+type Link = yew_router::components::Link<Route>;
+
 #[derive(Clone, PartialEq, Default, Deserialize, Serialize)]
 struct Query {
+    /// Older links keep the seed here instead of in the fragment.
+    #[serde(skip_serializing_if = "Option::is_none")]
     seed: Option<u64>,
+
+    speed: Option<Speed>,
+
+    /// The slug of the saved program this is a fork of, so saving it can record the lineage.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fork: Option<String>,
+}
+
+impl Query {
+    fn new(speed: Speed, fork: Option<String>) -> Self {
+        Self {
+            seed: None,
+            speed: Some(speed),
+            fork,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -26,58 +47,145 @@ pub fn Mosaic() -> Html {
 
     let location = use_location().unwrap();
     let history = BrowserHistory::new();
-    let view_state = use_state(|| ViewState::Run);
-
-    let update = use_state(|| Module::decode(location.hash()).unwrap_or_default());
+    let session = use_context::<Option<Session>>().unwrap();
+    let show_save = use_state(|| false);
 
-    let seed = use_state(|| {
-        let query = location.query::<Query>().unwrap_or_default();
-        query.seed.unwrap_or_else(rand::random)
+    // The URL is the source of truth for what to run, so these are recomputed whenever it
+    // changes (like after submitting the editor).
+    let share = use_memo(location.hash().to_owned(), |hash| {
+        Share::decode(hash).unwrap_or_default()
+    });
+    let update = &share.module;
+    let edges = share.edges;
+    let initial = &share.initial;
+
+    let query = location.query::<Query>().unwrap_or_default();
+    let random_seed = use_state(rand::random);
+    let seed = share.seed.or(query.seed).unwrap_or(*random_seed);
+    let speed = query.speed.unwrap_or_default();
+    let fork = query.fork;
+
+    // Forking starts out in the editor.
+    let view_state = use_state(|| {
+        if fork.is_some() {
+            ViewState::Edit
+        } else {
+            ViewState::Run
+        }
     });
 
+    // This also upgrades older links to the current format.
     use_effect_with((), {
         let history = history.clone();
-        let update = update.clone();
-        let seed = seed.clone();
+        let share = with_seed(&share, seed);
+        let query = Query::new(speed, fork.clone());
         move |_| {
-            replace_url(&history, &update, *seed).unwrap();
+            replace_url(&history, &share, &query).unwrap();
         }
     });
 
     use_body_class(vec!["h-screen", "w-screen"]);
 
-    tracing::debug!({ ?seed }, "Mosaic");
-    tracing::debug!("\n{}", *update);
+    tracing::debug!({ ?seed, ?edges, %initial }, "Mosaic");
+    tracing::debug!("\n{}", update);
 
     let show_editor = {
         let view_state = view_state.clone();
-        Callback::from(move |_| view_state.set(ViewState::Edit))
+        Callback::from(move |()| view_state.set(ViewState::Edit))
+    };
+
+    let onspeed = {
+        let history = history.clone();
+        let share = with_seed(&share, seed);
+        let fork = fork.clone();
+        Callback::from(move |speed| {
+            replace_url(&history, &share, &Query::new(speed, fork.clone())).unwrap()
+        })
+    };
+
+    // Painted blocks replace whatever the seed would have generated. The seed stays in the link
+    // because host functions like `random` still use it.
+    let onsaveinitial = {
+        let history = history.clone();
+        let share = with_seed(&share, seed);
+        let fork = fork.clone();
+        Callback::from(move |blocks: Blocks| {
+            let share = Share {
+                initial: Initial::Image(Box::new(blocks)),
+                ..share.clone()
+            };
+            push_url(&history, &share, &Query::new(speed, fork.clone())).unwrap()
+        })
     };
 
     let onsubmit = {
         let view_state = view_state.clone();
+        let fork = fork.clone();
 
         Callback::from(move |val: Option<SimulationEditorValue>| {
             tracing::debug!({ ?val }, "Editor result");
 
             if let Some(val) = val {
-                push_url(&history, &val.module, val.seed).unwrap();
+                let share = Share {
+                    module: val.module,
+                    seed: Some(val.seed),
+                    edges: val.edges,
+                    initial: val.initial,
+                    tests: val.tests,
+                };
+                push_url(&history, &share, &Query::new(speed, fork.clone())).unwrap();
             };
 
             view_state.set(ViewState::Run);
         })
     };
 
+    let save = match (&session, *show_save) {
+        (Some(session), true) => {
+            let share = with_seed(&share, seed).encode();
+            let onclose = {
+                let show_save = show_save.clone();
+                Callback::from(move |()| show_save.set(false))
+            };
+
+            html! {
+                <ProgramSave
+                    session={session.clone()}
+                    {share}
+                    parent={fork.clone()}
+                    {onclose}
+                    class="px-3 py-1"
+                />
+            }
+        }
+        _ => html! {},
+    };
+
     let inner = match *view_state {
-        ViewState::Run => html! {
-            <div class="flex justify-center items-center h-full container-size">
-                <div class="box-square">
-                    <Simulation update={update.binary.clone()} seed={*seed} class="h-full w-full"/>
-                </div>
-            </div>
-        },
+        ViewState::Run => html! { <>
+            {save}
+            <Simulation
+                update={update.binary.clone()}
+                {seed}
+                {edges}
+                initial={initial.clone()}
+                {speed}
+                {onspeed}
+                {onsaveinitial}
+                onedit={show_editor.clone()}
+                class="flex-grow min-h-0"
+            />
+        </> },
         ViewState::Edit => html! {
-            <SimulationEditor source={update.text.clone()} seed={*seed} {onsubmit} class="px-3 py-1" />
+            <SimulationEditor
+                module={update.clone()}
+                {seed}
+                {edges}
+                initial={initial.clone()}
+                tests={share.tests.clone()}
+                {onsubmit}
+                class="px-3 py-1"
+            />
         },
     };
 
@@ -85,7 +193,13 @@ pub fn Mosaic() -> Html {
         <div class="flex flex-col justify-between h-full w-full">
             <Header>
                 if *view_state == ViewState::Run {
-                    <button onclick={show_editor}>{"Edit"}</button>
+                    <button onclick={show_editor.reform(|_| ())}>{"Edit"}</button>
+
+                    if session.is_some() {
+                        <button onclick={move |_| show_save.set(true)}>{"Save"}</button>
+                        <Link to={Route::MosaicPrograms}>{"My programs"}</Link>
+                    }
+                    <Link to={Route::MosaicGallery}>{"Gallery"}</Link>
                 }
             </Header>
 
@@ -96,20 +210,25 @@ pub fn Mosaic() -> Html {
     }
 }
 
-fn replace_url(history: &BrowserHistory, module: &Module, seed: u64) -> eyre::Result<()> {
+fn replace_url(history: &BrowserHistory, share: &Share, query: &Query) -> eyre::Result<()> {
     history
-        .replace_with_query(
-            format!("{}#{}", Route::Mosaic.to_path(), module.encode()),
-            Query { seed: Some(seed) },
-        )
+        .replace_with_query(path(share), query)
         .wrap_err("replace history")
 }
 
-fn push_url(history: &BrowserHistory, module: &Module, seed: u64) -> eyre::Result<()> {
+fn push_url(history: &BrowserHistory, share: &Share, query: &Query) -> eyre::Result<()> {
     history
-        .push_with_query(
-            format!("{}#{}", Route::Mosaic.to_path(), module.encode()),
-            Query { seed: Some(seed) },
-        )
-        .wrap_err("replace history")
+        .push_with_query(path(share), query)
+        .wrap_err("push history")
+}
+
+fn with_seed(share: &Share, seed: u64) -> Share {
+    Share {
+        seed: Some(seed),
+        ..share.clone()
+    }
+}
+
+fn path(share: &Share) -> String {
+    format!("{}#{}", Route::Mosaic.to_path(), share.encode())
 }
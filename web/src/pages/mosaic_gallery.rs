@@ -0,0 +1,103 @@
+use gloo::history::{BrowserHistory, History};
+use time::macros::format_description;
+use yew::prelude::*;
+use yew::suspense::use_future;
+
+use crate::components::*;
+use crate::hooks::*;
+use crate::types::Program;
+use crate::Route;
+
+type Link = yew_router::components::Link<Route>;
+
+/// Public programs from everyone, newest first.
+#[function_component]
+pub fn MosaicGallery() -> Html {
+    use_title("Mosaic gallery");
+
+    let fallback = html! { <p>{"Loading gallery..."}</p> };
+
+    html! { <>
+        <Header>
+            <Link to={Route::Mosaic}>{"Mosaic"}</Link>
+        </Header>
+
+        <main class="m-3">
+            <h1>{"Gallery"}</h1>
+
+            <Suspense {fallback}>
+                <GalleryList />
+            </Suspense>
+        </main>
+
+        <Footer />
+    </> }
+}
+
+#[function_component]
+fn GalleryList() -> HtmlResult {
+    let programs = use_future(Program::gallery)?;
+
+    let programs = match &*programs {
+        Ok(programs) => programs,
+        Err(err) => {
+            return Ok(html! {
+                <Error error={err.to_string()}>
+                    <p>{"Could not load the gallery."}</p>
+                </Error>
+            })
+        }
+    };
+
+    if programs.is_empty() {
+        return Ok(html! {
+            <p>{"Nothing here yet! Save a program with \"Show in the gallery\" checked to add it."}</p>
+        });
+    }
+
+    Ok(html! {
+        <ul class="grid grid-cols-2 sm:grid-cols-3 lg:grid-cols-5 gap-4">
+            { for programs.iter().map(|program| html! {
+                <GalleryCard program={program.clone()} />
+            }) }
+        </ul>
+    })
+}
+
+#[derive(Properties, PartialEq, Debug)]
+struct GalleryCardProps {
+    program: Program,
+}
+
+#[function_component]
+fn GalleryCard(props: &GalleryCardProps) -> Html {
+    let program = &props.program;
+
+    let date = format_description!("[year]-[month]-[day]");
+    let created = program.created_at.format(date).unwrap_or_default();
+
+    let onfork = {
+        let path = program.fork_path();
+        Callback::from(move |_| BrowserHistory::new().push(&path))
+    };
+
+    html! {
+        <li class="flex flex-col gap-1">
+            <ProgramThumbnail share={program.share.clone()} class="w-full" />
+
+            <Link to={program.route()}>{&program.name}</Link>
+            <p class="text-sm">
+                {"by "}{program.author.as_deref().unwrap_or("someone")}
+                {" on "}<span class="tabular-nums">{created}</span>
+            </p>
+
+            if let Some(parent) = &program.parent {
+                <p class="text-sm">
+                    {"forked from "}<Link to={parent.route()}>{&parent.name}</Link>
+                </p>
+            }
+
+            <button type="button" onclick={onfork}>{"Fork"}</button>
+        </li>
+    }
+}
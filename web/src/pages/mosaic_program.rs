@@ -0,0 +1,69 @@
+use gloo::history::{BrowserHistory, History};
+use yew::prelude::*;
+use yew::suspense::use_future_with;
+
+use crate::components::*;
+use crate::hooks::*;
+use crate::types::Program;
+use crate::Route;
+
+type Link = yew_router::components::Link<Route>;
+
+#[derive(Properties, PartialEq, Debug)]
+pub struct MosaicProgramProps {
+    pub slug: String,
+}
+
+/// Follows a short link to a saved program by going to its full share link.
+#[function_component]
+pub fn MosaicProgram(props: &MosaicProgramProps) -> Html {
+    use_title("Mosaic");
+
+    let fallback = html! { <p>{"Loading program..."}</p> };
+
+    html! { <>
+        <Header />
+
+        <main class="m-3 max-w-prose">
+            <Suspense {fallback}>
+                <Redirect slug={props.slug.clone()} />
+            </Suspense>
+        </main>
+
+        <Footer />
+    </> }
+}
+
+#[function_component]
+fn Redirect(props: &MosaicProgramProps) -> HtmlResult {
+    let program = use_future_with(props.slug.clone(), |slug| async move {
+        Program::load(&slug).await
+    })?;
+
+    let path = match &*program {
+        Ok(Some(program)) => Some(program.long_path()),
+        _ => None,
+    };
+
+    use_effect_with(path, |path| {
+        if let Some(path) = path {
+            BrowserHistory::new().replace(path);
+        }
+    });
+
+    match &*program {
+        Ok(Some(_)) => Ok(html! { <p>{"Loading program..."}</p> }),
+        Ok(None) => Ok(html! {
+            <>
+                <h1>{"Not Found"}</h1>
+                <p>{"There's no saved program at this link. Maybe it was deleted?"}</p>
+                <p><Link to={Route::Mosaic}>{"Go to Mosaic"}</Link></p>
+            </>
+        }),
+        Err(err) => Ok(html! {
+            <Error error={err.to_string()}>
+                <p>{"Could not load the program."}</p>
+            </Error>
+        }),
+    }
+}
@@ -0,0 +1,164 @@
+use time::macros::format_description;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+use yew::suspense::use_future_with;
+
+use crate::components::*;
+use crate::hooks::*;
+use crate::types::{Program, Session};
+use crate::Route;
+
+type Link = yew_router::components::Link<Route>;
+
+/// Lists the logged-in user's saved programs.
+#[function_component]
+pub fn MosaicPrograms() -> Html {
+    use_title("My Mosaic programs");
+
+    let session = use_context::<Option<Session>>().unwrap();
+
+    let fallback = html! { <p>{"Loading programs..."}</p> };
+
+    let inner = match session {
+        Some(session) => html! {
+            <Suspense {fallback}>
+                <ProgramList {session} />
+            </Suspense>
+        },
+        None => html! {
+            <p>{"Log in to see your saved programs."}</p>
+        },
+    };
+
+    html! { <>
+        <Header>
+            <Link to={Route::Mosaic}>{"Mosaic"}</Link>
+        </Header>
+
+        <main class="m-3 max-w-prose">
+            <h1>{"My programs"}</h1>
+
+            {inner}
+        </main>
+
+        <Footer />
+    </> }
+}
+
+#[derive(Properties, PartialEq, Debug)]
+struct ProgramListProps {
+    session: Session,
+}
+
+#[function_component]
+fn ProgramList(props: &ProgramListProps) -> HtmlResult {
+    // Bumped after each change to fetch the list again.
+    let version = use_state(|| 0u32);
+    let error = use_state(|| None::<String>);
+
+    let programs = use_future_with(*version, |_| Program::list())?;
+
+    let programs = match &*programs {
+        Ok(programs) => programs,
+        Err(err) => {
+            return Ok(html! {
+                <Error error={err.to_string()}>
+                    <p>{"Could not load your programs."}</p>
+                </Error>
+            })
+        }
+    };
+
+    if programs.is_empty() {
+        return Ok(html! {
+            <p>
+                {"You haven't saved any programs yet. Use the Save button in "}
+                <Link to={Route::Mosaic}>{"Mosaic"}</Link>
+                {" to make a short link to one."}
+            </p>
+        });
+    }
+
+    let date = format_description!("[year]-[month]-[day]");
+
+    // Runs a change on the server, then fetches the list again to show it.
+    let onchange = |program: &Program, change: Change| {
+        let session = props.session.clone();
+        let slug = program.slug.clone();
+        let version = version.clone();
+        let error = error.clone();
+
+        Callback::from(move |_| {
+            let session = session.clone();
+            let slug = slug.clone();
+            let version = version.clone();
+            let error = error.clone();
+
+            spawn_local(async move {
+                match change.apply(&session, &slug).await {
+                    Ok(()) => {
+                        error.set(None);
+                        version.set(*version + 1);
+                    }
+                    Err(err) => {
+                        tracing::error!({ ?err, ?change }, "change program");
+                        error.set(Some(err.to_string()));
+                    }
+                }
+            });
+        })
+    };
+
+    let row = |program: &Program| {
+        let ontoggle = onchange(program, Change::SetPublic(!program.public));
+        let ondelete = onchange(program, Change::Delete);
+
+        let created = program.created_at.format(date).unwrap_or_default();
+
+        html! {
+            <li class="flex flex-row flex-wrap justify-between items-center gap-2">
+                <Link to={program.route()}>{&program.name}</Link>
+                if let Some(parent) = &program.parent {
+                    <span class="text-sm">
+                        {"forked from "}<Link to={parent.route()}>{&parent.name}</Link>
+                    </span>
+                }
+                <span class="flex-grow tabular-nums">{created}</span>
+                <button type="button" onclick={ontoggle}>
+                    { if program.public { "Hide from gallery" } else { "Show in gallery" } }
+                </button>
+                <button type="button" onclick={ondelete}>{"Delete"}</button>
+            </li>
+        }
+    };
+
+    Ok(html! { <>
+        if let Some(err) = &*error {
+            <Error error={err.clone()}>
+                <p>{"Could not update the program."}</p>
+            </Error>
+        }
+
+        <ul class="space-y-1">
+            { for programs.iter().map(row) }
+        </ul>
+    </> })
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Change {
+    SetPublic(bool),
+    Delete,
+}
+
+impl Change {
+    async fn apply(self, session: &Session, slug: &str) -> eyre::Result<()> {
+        match self {
+            Change::SetPublic(public) => {
+                Program::set_public(session, slug, public).await?;
+                Ok(())
+            }
+            Change::Delete => Program::delete(session, slug).await,
+        }
+    }
+}
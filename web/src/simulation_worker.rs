@@ -0,0 +1,293 @@
+//! Steps a Mosaic simulation off the main thread, so a slow rule doesn't freeze the page.
+//!
+//! The component that spawns this owns every frame. It sends the frame to continue from along
+//! with each command, so painting, stepping back, and resetting are all just a different frame
+//! to continue from.
+
+use gloo::timers::callback::Interval;
+use gloo::worker::{HandlerId, Worker, WorkerScope};
+use mosaic::{Blocks, EdgeMode, Interpreter, States};
+use serde::{Deserialize, Serialize};
+
+/// Where the worker's script ends up, as built by the `data-bin` link in `index.html`.
+pub const SCRIPT_PATH: &str = "/simulation_worker.js";
+
+/// The state of the grid after one tick.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Frame {
+    pub prev: Blocks,
+    pub next: Blocks,
+
+    /// The hidden state behind `next`, for modules that keep any.
+    pub states: States,
+
+    pub tick: u64,
+}
+
+impl Frame {
+    /// The frame before the first tick, starting from `next`.
+    pub fn initial(next: Blocks) -> Self {
+        Self {
+            prev: Blocks::default(),
+            next,
+            states: States::from(&next),
+            tick: 0,
+        }
+    }
+}
+
+/// A command for the worker. Each one has a generation number, which the worker copies onto what
+/// it sends back, so the component can tell which results are from before its latest command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SimulationInput {
+    /// Stops running and switches to a new module. Nothing happens until the next `Play` or
+    /// `Step`, which also gets the error if the module couldn't be loaded.
+    Load {
+        generation: u64,
+        update: Vec<u8>,
+        seed: u64,
+        edges: EdgeMode,
+    },
+
+    /// Computes a tick after `from`, and another after that every `interval_millis`.
+    Play {
+        generation: u64,
+        from: Box<Frame>,
+        interval_millis: u32,
+    },
+
+    /// Computes one tick after `from`, and stops running if it was.
+    Step { generation: u64, from: Box<Frame> },
+
+    /// Stops running.
+    Pause { generation: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SimulationOutput {
+    Frame {
+        generation: u64,
+        frame: Box<Frame>,
+    },
+
+    /// The module couldn't be loaded or a tick failed. Either way, the worker stops running until
+    /// it gets a new module.
+    Error {
+        generation: u64,
+        message: String,
+    },
+}
+
+pub struct SimulationWorker {
+    simulator: Simulator,
+    interval: Option<Interval>,
+
+    /// Who to send results to, and which command they're for.
+    handler: Option<HandlerId>,
+    generation: u64,
+}
+
+pub enum SimulationWorkerMsg {
+    Tick,
+}
+
+impl Worker for SimulationWorker {
+    type Message = SimulationWorkerMsg;
+
+    type Input = SimulationInput;
+
+    type Output = SimulationOutput;
+
+    fn create(_scope: &WorkerScope<Self>) -> Self {
+        Self {
+            simulator: Simulator::default(),
+            interval: None,
+            handler: None,
+            generation: 0,
+        }
+    }
+
+    fn update(&mut self, scope: &WorkerScope<Self>, msg: Self::Message) {
+        match msg {
+            SimulationWorkerMsg::Tick => self.step(scope),
+        }
+    }
+
+    fn received(&mut self, scope: &WorkerScope<Self>, msg: Self::Input, id: HandlerId) {
+        self.handler = Some(id);
+        self.interval = None;
+
+        match msg {
+            SimulationInput::Load {
+                generation,
+                update,
+                seed,
+                edges,
+            } => {
+                self.generation = generation;
+
+                if let Err(message) = self.simulator.load(&update, seed, edges) {
+                    self.respond(scope, |generation| SimulationOutput::Error {
+                        generation,
+                        message,
+                    });
+                }
+            }
+
+            SimulationInput::Play {
+                generation,
+                from,
+                interval_millis,
+            } => {
+                self.generation = generation;
+                self.simulator.frame = Some(*from);
+                self.step(scope);
+
+                if self.simulator.is_running() {
+                    let scope = scope.clone();
+                    self.interval = Some(Interval::new(interval_millis, move || {
+                        scope.send_message(SimulationWorkerMsg::Tick)
+                    }));
+                }
+            }
+
+            SimulationInput::Step { generation, from } => {
+                self.generation = generation;
+                self.simulator.frame = Some(*from);
+                self.step(scope);
+            }
+
+            SimulationInput::Pause { generation } => self.generation = generation,
+        }
+    }
+}
+
+impl SimulationWorker {
+    /// Computes the tick after the current frame and sends it back, or sends the error that's
+    /// keeping the module from running.
+    fn step(&mut self, scope: &WorkerScope<Self>) {
+        match self.simulator.step() {
+            None => {}
+            Some(Ok(frame)) => self.respond(scope, |generation| SimulationOutput::Frame {
+                generation,
+                frame: Box::new(frame),
+            }),
+            Some(Err(message)) => {
+                self.interval = None;
+                self.respond(scope, |generation| SimulationOutput::Error {
+                    generation,
+                    message,
+                });
+            }
+        }
+    }
+
+    fn respond(&self, scope: &WorkerScope<Self>, output: impl FnOnce(u64) -> SimulationOutput) {
+        if let Some(handler) = self.handler {
+            scope.respond(handler, output(self.generation));
+        }
+    }
+}
+
+/// The module and frame the worker is running, apart from its timer and messages.
+#[derive(Default)]
+struct Simulator {
+    interpreter: Option<Interpreter>,
+    frame: Option<Frame>,
+
+    /// Why the module can't run, from loading it or from a failed tick. This is sent again for
+    /// every later `Play` or `Step`, since the component ignores replies to commands older than
+    /// its latest one, and `Load` is always followed by another command.
+    error: Option<String>,
+}
+
+impl Simulator {
+    fn load(&mut self, update: &[u8], seed: u64, edges: EdgeMode) -> Result<(), String> {
+        self.frame = None;
+
+        match Interpreter::new(update, seed) {
+            Ok(interpreter) => {
+                self.interpreter = Some(interpreter.with_edges(edges));
+                self.error = None;
+                Ok(())
+            }
+            Err(err) => {
+                self.interpreter = None;
+                self.error = Some(err.to_string());
+                Err(err.to_string())
+            }
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.interpreter.is_some()
+    }
+
+    /// Computes the tick after the current frame. A failure drops the interpreter, so nothing else
+    /// runs until the next `Load`.
+    fn step(&mut self) -> Option<Result<Frame, String>> {
+        if let Some(err) = &self.error {
+            return Some(Err(err.clone()));
+        }
+
+        let (Some(interpreter), Some(frame)) = (&mut self.interpreter, &self.frame) else {
+            return None;
+        };
+
+        let tick = frame.tick + 1;
+        let prev = frame.next;
+
+        match interpreter.step_with_states(&prev, &frame.states, tick) {
+            Ok((next, states)) => {
+                let frame = Frame {
+                    prev,
+                    next,
+                    states,
+                    tick,
+                };
+                self.frame = Some(frame);
+                Some(Ok(frame))
+            }
+            Err(err) => {
+                self.interpreter = None;
+                self.error = Some(err.to_string());
+                Some(Err(err.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mosaic::Module;
+
+    use super::*;
+
+    #[test]
+    fn load_errors_are_sent_again_when_playing() {
+        let no_next = Module::new(String::from("(module (func (export \"prev\")))"))
+            .unwrap()
+            .binary;
+
+        let mut simulator = Simulator::default();
+        let err = simulator
+            .load(&no_next, 0, EdgeMode::default())
+            .unwrap_err();
+        assert_eq!(err, "module does not export a `next` function");
+
+        // The component only listens for replies to the `Play` or `Step` that comes after.
+        simulator.frame = Some(Frame::initial(Blocks::from_seed(0)));
+        assert!(!simulator.is_running());
+        assert_eq!(simulator.step(), Some(Err(err.clone())));
+        assert_eq!(simulator.step(), Some(Err(err)));
+
+        // A working module clears the error.
+        let module = Module::default();
+        simulator
+            .load(&module.binary, 0, EdgeMode::default())
+            .unwrap();
+        simulator.frame = Some(Frame::initial(Blocks::from_seed(0)));
+        let frame = simulator.step().unwrap().unwrap();
+        assert_eq!(frame.tick, 1);
+    }
+}
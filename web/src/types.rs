@@ -6,4 +6,19 @@ macro_rules! type_ {
 }
 
 type_!(csrf_token);
+type_!(program);
 type_!(session);
+type_!(trellis_secrets);
+
+use gloo::net::http::Response;
+
+/// Turns an error response into an error with the server's explanation (if it gave one).
+pub(crate) async fn check(res: Response) -> eyre::Result<Response> {
+    if res.ok() {
+        return Ok(res);
+    }
+
+    let status = res.status();
+    let text = res.text().await.unwrap_or_default();
+    Err(eyre::eyre!("error response ({status}): {text}"))
+}
@@ -2,6 +2,8 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
+pub const CSRF_TOKEN_HEADER: &str = "X-Csrf-Token";
+
 #[derive(Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct CsrfToken(String);
 
@@ -0,0 +1,136 @@
+use gloo::net::http::Request;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use yew_router::Routable;
+
+use crate::types::{check, Session, CSRF_TOKEN_HEADER};
+use crate::Route;
+
+/// A Mosaic program saved on the server, which gives its share link a short URL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Program {
+    pub slug: String,
+    pub name: String,
+
+    /// The share link fragment, which has the module and its settings.
+    pub share: String,
+
+    /// Whether the program is listed in the gallery. Anyone with the link can see it either way.
+    pub public: bool,
+
+    pub author: Option<String>,
+
+    /// The program this one was forked from, if it still exists.
+    pub parent: Option<ProgramRef>,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgramRef {
+    pub slug: String,
+    pub name: String,
+}
+
+impl ProgramRef {
+    pub fn route(&self) -> Route {
+        Route::MosaicProgram {
+            slug: self.slug.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NewProgram {
+    pub name: String,
+    pub share: String,
+    pub public: bool,
+
+    /// The slug of the program this was forked from.
+    pub parent: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct ProgramUpdate {
+    public: bool,
+}
+
+impl Program {
+    /// The short link to this program.
+    pub fn route(&self) -> Route {
+        Route::MosaicProgram {
+            slug: self.slug.clone(),
+        }
+    }
+
+    /// The page that runs this program, with everything in the URL.
+    pub fn long_path(&self) -> String {
+        format!("{}#{}", Route::Mosaic.to_path(), self.share)
+    }
+
+    /// The page that opens this program in the editor to make a new one based on it.
+    pub fn fork_path(&self) -> String {
+        format!("{}?fork={}#{}", Route::Mosaic.to_path(), self.slug, self.share)
+    }
+
+    /// Lists the logged-in user's programs, newest first.
+    pub async fn list() -> eyre::Result<Vec<Self>> {
+        let res = Request::get("/api/mosaic/programs").send().await?;
+        let res = check(res).await?;
+        Ok(res.json().await?)
+    }
+
+    /// Lists the newest public programs from everyone.
+    pub async fn gallery() -> eyre::Result<Vec<Self>> {
+        let res = Request::get("/api/mosaic/gallery").send().await?;
+        let res = check(res).await?;
+        Ok(res.json().await?)
+    }
+
+    pub async fn load(slug: &str) -> eyre::Result<Option<Self>> {
+        let res = Request::get(&format!("/api/mosaic/programs/{slug}"))
+            .send()
+            .await?;
+
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let res = check(res).await?;
+        Ok(Some(res.json().await?))
+    }
+
+    pub async fn create(session: &Session, new: &NewProgram) -> eyre::Result<Self> {
+        let res = Request::post("/api/mosaic/programs")
+            .header(CSRF_TOKEN_HEADER, session.csrf_token.secret())
+            .json(new)?
+            .send()
+            .await?;
+
+        let res = check(res).await?;
+        Ok(res.json().await?)
+    }
+
+    pub async fn set_public(session: &Session, slug: &str, public: bool) -> eyre::Result<Self> {
+        let res = Request::patch(&format!("/api/mosaic/programs/{slug}"))
+            .header(CSRF_TOKEN_HEADER, session.csrf_token.secret())
+            .json(&ProgramUpdate { public })?
+            .send()
+            .await?;
+
+        let res = check(res).await?;
+        Ok(res.json().await?)
+    }
+
+    pub async fn delete(session: &Session, slug: &str) -> eyre::Result<()> {
+        let res = Request::delete(&format!("/api/mosaic/programs/{slug}"))
+            .header(CSRF_TOKEN_HEADER, session.csrf_token.secret())
+            .send()
+            .await?;
+
+        check(res).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use gloo::net::http::Request;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{check, Session, CSRF_TOKEN_HEADER};
+
+/// Which Trellis secrets the logged-in user has saved on the server. The secrets themselves never
+/// come back once they're saved.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct TrellisSecrets {
+    pub open_weather: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct SecretsUpdate<'a> {
+    open_weather: Option<&'a str>,
+}
+
+impl TrellisSecrets {
+    pub async fn load() -> eyre::Result<Self> {
+        let res = Request::get("/api/trellis/secrets").send().await?;
+        let res = check(res).await?;
+        Ok(res.json().await?)
+    }
+
+    /// Saves an OpenWeatherMap API key from before keys were saved on the server, unless one has
+    /// been saved on the server since.
+    pub async fn upload_legacy_open_weather(session: &Session, key: &str) -> eyre::Result<Self> {
+        let secrets = Self::load().await?;
+        if secrets.open_weather {
+            return Ok(secrets);
+        }
+
+        Self::save_open_weather(session, Some(key)).await
+    }
+
+    /// Saves a new OpenWeatherMap API key, or forgets the saved one.
+    pub async fn save_open_weather(session: &Session, key: Option<&str>) -> eyre::Result<Self> {
+        let res = Request::put("/api/trellis/secrets")
+            .header(CSRF_TOKEN_HEADER, session.csrf_token.secret())
+            .json(&SecretsUpdate { open_weather: key })?
+            .send()
+            .await?;
+
+        let res = check(res).await?;
+        Ok(res.json().await?)
+    }
+}